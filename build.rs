@@ -19,6 +19,7 @@ fn main() {
 
     let sdl2_path = "sdl/SDL2-devel-2.26.5-VC/SDL2-2.26.5";
     let sdl2_image_path = "sdl/SDL2_image-devel-2.6.3-VC/SDL2_image-2.6.3";
+    let sdl2_mixer_path = "sdl/SDL2_mixer-devel-2.6.3-VC/SDL2_mixer-2.6.3";
 
     // Generate bindings for SDL.h
     let bindings = bindgen::Builder::default()
@@ -60,6 +61,31 @@ fn main() {
         .write_to_file(out_dir.join("sdl2_image_bindings.rs"))
         .expect("Error writing SDL2_Image bindings to file");
 
+    // Generate bindings for SDL_mixer.h
+    let bindings = bindgen::Builder::default()
+        .header(format!("{}/include/SDL_mixer.h", sdl2_mixer_path))
+        .clang_arg(format!("-I{}/include", sdl2_path))
+        .clang_arg("-Wno-everything")
+        .generate_comments(false)
+        .default_enum_style(bindgen::EnumVariation::Rust {
+            non_exhaustive: false,
+        })
+        .parse_callbacks(Box::new(MyCallbacks))
+        .raw_line("use num_derive::FromPrimitive;")
+        .raw_line("use crate::sdl2::*;")
+        .allowlist_type("_?Mix_.*")
+        .allowlist_type("MIX_.*")
+        .allowlist_function("Mix_.*")
+        .allowlist_var("MIX_.*")
+        .allowlist_recursively(false)
+        .generate()
+        .expect("Unable to generate bindings for SDL2_mixer");
+
+    // Write the bindings to a file
+    bindings
+        .write_to_file(out_dir.join("sdl2_mixer_bindings.rs"))
+        .expect("Error writing SDL2_mixer bindings to file");
+
     // Link to the SDL2 library
     println!("cargo:rustc-link-search={}/lib/x64", sdl2_path);
     println!("cargo:rustc-link-lib=SDL2");
@@ -73,4 +99,12 @@ fn main() {
         "cargo:rerun-if-changed={}/includes/SDL_Image.h",
         sdl2_image_path
     );
+
+    // Link to the SDL2_mixer library
+    println!("cargo:rustc-link-search={}/lib/x64", sdl2_mixer_path);
+    println!("cargo:rustc-link-lib=SDL2_mixer");
+    println!(
+        "cargo:rerun-if-changed={}/includes/SDL_mixer.h",
+        sdl2_mixer_path
+    );
 }
@@ -1,59 +1,400 @@
+use crate::asset_pack::AssetPack;
+use crate::audio::{Music, MusicAccess, Sound, SoundAccess};
+use crate::error::{Error, Result};
+use crate::manifest::{AssetGroup, AssetManifest};
 use crate::pointers::*;
 use crate::sdl2;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+// Why RenderSystem::preload_group() failed
+#[derive(Debug, Clone)]
+pub enum PreloadError {
+    UnknownGroup(String),
+    // Every file that is missing or failed to load, nothing of the group stays loaded
+    Files(Vec<(String, Error)>),
+}
+
+impl fmt::Display for PreloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PreloadError::UnknownGroup(group) => write!(f, "No group named {}", group),
+            PreloadError::Files(files) => {
+                for (i, (file, e)) in files.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", file, e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
 
 pub struct AssetManager {
-    file_imgs: HashMap<&'static str, Texture>,
+    file_imgs: HashMap<String, Texture>,
+    file_sounds: HashMap<String, Sound>,
+    file_music: HashMap<String, Music>,
+    packs: Vec<AssetPack>,
+    manifest: AssetManifest,
+    loaded_groups: HashSet<String>,
+    // Removed while playing, freed once they stop
+    released_sounds: Vec<Sound>,
+    released_music: Vec<Music>,
 }
 
 impl AssetManager {
     pub fn new() -> Self {
         AssetManager {
             file_imgs: HashMap::new(),
+            file_sounds: HashMap::new(),
+            file_music: HashMap::new(),
+            packs: Vec::new(),
+            manifest: AssetManifest::new(),
+            loaded_groups: HashSet::new(),
+            released_sounds: Vec::new(),
+            released_music: Vec::new(),
         }
     }
 
-    pub fn get_image(&self, file: &'static str) -> Option<TextureAccess> {
+    pub fn add_manifest(&mut self, manifest: AssetManifest) {
+        self.manifest.extend(manifest);
+    }
+
+    pub fn get_group(&self, group: &str) -> Option<&AssetGroup> {
+        self.manifest.get_group(group)
+    }
+
+    pub fn group_loaded(&self, group: &str) -> bool {
+        self.loaded_groups.contains(group)
+    }
+
+    // Whether a file can be found in a pack or on disk
+    pub fn exists(&self, file: &str) -> bool {
+        self.get_packed(file).is_some() || Path::new(file).is_file()
+    }
+
+    pub fn in_loaded_group(&self, file: &str) -> bool {
+        self.loaded_groups.iter().any(|group| {
+            self.get_group(group)
+                .map_or(false, |g| g.files().any(|f| f == file))
+        })
+    }
+
+    // Files in group that can't be found, None if the group doesn't exist
+    pub fn missing_files(&self, group: &str) -> Option<Vec<String>> {
+        self.get_group(group).map(|g| {
+            g.files()
+                .filter(|file| !self.exists(file))
+                .cloned()
+                .collect()
+        })
+    }
+
+    // Packs added later take priority over earlier ones
+    pub fn add_pack(&mut self, pack: AssetPack) {
+        self.packs.push(pack);
+    }
+
+    pub fn get_packed(&self, file: &str) -> Option<&[u8]> {
+        self.packs.iter().rev().find_map(|pack| pack.get(file))
+    }
+
+    pub fn get_image(&self, file: &str) -> Option<TextureAccess> {
         match self.file_imgs.get(file) {
             Some(tex) => Some(tex.access()),
             None => None,
         }
     }
 
-    pub fn add_image(&mut self, file: &'static str, tex: Texture) {
-        self.file_imgs.insert(file, tex);
+    pub fn add_image(&mut self, file: &str, tex: Texture) {
+        self.file_imgs.insert(file.to_string(), tex);
+    }
+
+    // TextureAccess handles to the image become invalid and draw nothing
+    pub fn remove_image(&mut self, file: &str) {
+        self.file_imgs.remove(file);
+    }
+
+    pub fn get_sound(&mut self, file: &str) -> Option<SoundAccess> {
+        match self.load_sound(file) {
+            Ok(sound) => Some(sound),
+            Err(e) => {
+                println!(
+                    "AssetManager::get_sound() - Unable to open file {}: {}",
+                    file, e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn load_sound(&mut self, file: &str) -> Result<SoundAccess> {
+        if let Some(sound) = self.file_sounds.get(file) {
+            return Ok(sound.access());
+        }
+        let sound = match self.get_packed(file) {
+            Some(bytes) => Sound::from_bytes(bytes),
+            None => Sound::new(file),
+        }?;
+        let access = sound.access();
+        self.file_sounds.insert(file.to_string(), sound);
+        Ok(access)
+    }
+
+    // A playing sound is freed once it stops, until then its handles stay valid
+    pub fn remove_sound(&mut self, file: &str) {
+        self.free_released();
+        if let Some(sound) = self.file_sounds.remove(file) {
+            if sound.playing() {
+                self.released_sounds.push(sound);
+            }
+        }
+    }
+
+    pub fn get_music(&mut self, file: &str) -> Option<MusicAccess> {
+        match self.load_music(file) {
+            Ok(music) => Some(music),
+            Err(e) => {
+                println!(
+                    "AssetManager::get_music() - Unable to open file {}: {}",
+                    file, e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn load_music(&mut self, file: &str) -> Result<MusicAccess> {
+        if let Some(music) = self.file_music.get(file) {
+            return Ok(music.access());
+        }
+        let music = match self.get_packed(file) {
+            Some(bytes) => Music::from_bytes(bytes),
+            None => Music::new(file),
+        }?;
+        let access = music.access();
+        self.file_music.insert(file.to_string(), music);
+        Ok(access)
+    }
+
+    // Like remove_sound(), playing music is freed once it stops
+    pub fn remove_music(&mut self, file: &str) {
+        self.free_released();
+        if let Some(music) = self.file_music.remove(file) {
+            if music.playing() {
+                self.released_music.push(music);
+            }
+        }
+    }
+
+    // Free removed sounds and music that have stopped since
+    pub fn free_released(&mut self) {
+        self.released_sounds.retain(|s| s.playing());
+        self.released_music.retain(|m| m.playing());
     }
 }
 
+const PLACEHOLDER_SIZE: i32 = 64;
+const PLACEHOLDER_TILE_SIZE: i32 = 8;
+
 pub struct RenderSystem {
     win: Window,
     pub r: Renderer,
     pub am: AssetManager,
+    placeholder: Option<Texture>,
 }
 
 impl RenderSystem {
-    pub fn new(win: Window) -> Self {
-        let r = Renderer::new(&win);
-        RenderSystem {
+    pub fn new(win: Window) -> Result<Self> {
+        let r = Renderer::new(&win)?;
+        Ok(RenderSystem {
             win,
             r,
             am: AssetManager::new(),
+            placeholder: None,
+        })
+    }
+
+    pub fn win(&self) -> &Window {
+        &self.win
+    }
+
+    // Id of the window this draws to, used to route events with EventRouter
+    pub fn id(&self) -> u32 {
+        self.win.id()
+    }
+
+    // Return a checkerboard texture instead of None for images that fail to load
+    pub fn enable_placeholder(&mut self) -> Result<()> {
+        if self.placeholder.is_none() {
+            self.placeholder = Some(Texture::checkerboard(
+                &self.r,
+                PLACEHOLDER_SIZE,
+                PLACEHOLDER_TILE_SIZE,
+            )?);
         }
+        Ok(())
     }
 
-    pub fn get_image(&mut self, file: &'static str) -> Option<TextureAccess> {
-        match self.am.get_image(file) {
+    pub fn disable_placeholder(&mut self) {
+        self.placeholder = None;
+    }
+
+    pub fn placeholder(&self) -> Option<TextureAccess> {
+        self.placeholder.as_ref().map(|tex| tex.access())
+    }
+
+    pub fn get_image(&mut self, file: &str) -> Option<TextureAccess> {
+        match self.load_image(file) {
+            Ok(tex) => Some(tex),
+            Err(e) => {
+                println!(
+                    "RenderSystem::get_image() - Unable to open file {}: {}",
+                    file, e
+                );
+                self.placeholder()
+            }
+        }
+    }
+
+    // Like get_image() but without the placeholder
+    pub fn load_image(&mut self, file: &str) -> Result<TextureAccess> {
+        if let Some(tex) = self.am.get_image(file) {
+            return Ok(tex);
+        }
+        let tex = match self.am.get_packed(file) {
+            Some(bytes) => Texture::from_bytes(&self.r, bytes),
+            None => Texture::new(&self.r, file),
+        }?;
+        let access = tex.access();
+        self.am.add_image(file, tex);
+        Ok(access)
+    }
+
+    // Load an image from memory, cached under name
+    pub fn get_image_from_bytes(&mut self, name: &str, bytes: &[u8]) -> Option<TextureAccess> {
+        match self.am.get_image(name) {
             Some(tex) => Some(tex),
-            None => {
-                self.am.add_image(file, Texture::new(&self.r, file));
-                match self.am.get_image(file) {
-                    Some(tex) => Some(tex),
-                    None => {
-                        println!("RenderSystem::get_image() - Unable to open file {}", file);
-                        None
-                    }
+            None => match Texture::from_bytes(&self.r, bytes) {
+                Ok(tex) => {
+                    self.am.add_image(name, tex);
+                    self.am.get_image(name)
+                }
+                Err(e) => {
+                    println!(
+                        "RenderSystem::get_image_from_bytes() - Unable to load {}: {}",
+                        name, e
+                    );
+                    self.placeholder()
                 }
+            },
+        }
+    }
+
+    // Load every asset in a manifest group, all files that are missing or fail to load
+    // are reported at once and the group is left unloaded
+    pub fn preload_group(&mut self, group: &str) -> Result<(), PreloadError> {
+        let files = match self.am.get_group(group) {
+            Some(g) => g.clone(),
+            None => {
+                println!("RenderSystem::preload_group() - No group named {}", group);
+                return Err(PreloadError::UnknownGroup(group.to_string()));
+            }
+        };
+        let mut failed = Vec::new();
+        // Files loaded by this call, unloaded again on failure
+        let mut loaded = AssetGroup::default();
+        for file in files.images.iter().chain(files.atlases.iter()) {
+            if self.am.file_imgs.contains_key(file) {
+                continue;
+            }
+            match self.preload_file(file, |rs| rs.load_image(file).map(|_| ())) {
+                Ok(()) => loaded.images.push(file.clone()),
+                Err(e) => failed.push((file.clone(), e)),
+            }
+        }
+        for file in &files.fonts {
+            failed.push((
+                file.clone(),
+                Error::new("RenderSystem::preload_group()", "Fonts aren't supported"),
+            ));
+        }
+        for file in &files.sounds {
+            if self.am.file_sounds.contains_key(file) {
+                continue;
+            }
+            match self.preload_file(file, |rs| rs.am.load_sound(file).map(|_| ())) {
+                Ok(()) => loaded.sounds.push(file.clone()),
+                Err(e) => failed.push((file.clone(), e)),
+            }
+        }
+        for file in &files.music {
+            if self.am.file_music.contains_key(file) {
+                continue;
+            }
+            match self.preload_file(file, |rs| rs.am.load_music(file).map(|_| ())) {
+                Ok(()) => loaded.music.push(file.clone()),
+                Err(e) => failed.push((file.clone(), e)),
+            }
+        }
+        if failed.is_empty() {
+            self.am.loaded_groups.insert(group.to_string());
+            Ok(())
+        } else {
+            self.unload_files(&loaded);
+            let e = PreloadError::Files(failed);
+            println!(
+                "RenderSystem::preload_group() - Unable to load group {}: {}",
+                group, e
+            );
+            Err(e)
+        }
+    }
+
+    fn preload_file(
+        &mut self,
+        file: &str,
+        load: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        if self.am.exists(file) {
+            load(self)
+        } else {
+            Err(Error::new(
+                "RenderSystem::preload_group()",
+                "File not found",
+            ))
+        }
+    }
+
+    // Unload a group's assets unless another loaded group still uses them
+    // Handles to released images become invalid, see TextureAccess::valid()
+    pub fn release_group(&mut self, group: &str) {
+        if !self.am.loaded_groups.remove(group) {
+            return;
+        }
+        if let Some(files) = self.am.get_group(group).cloned() {
+            self.unload_files(&files);
+        }
+    }
+
+    fn unload_files(&mut self, files: &AssetGroup) {
+        for file in files.images.iter().chain(files.atlases.iter()) {
+            if !self.am.in_loaded_group(file) {
+                self.am.remove_image(file);
+            }
+        }
+        for file in &files.sounds {
+            if !self.am.in_loaded_group(file) {
+                self.am.remove_sound(file);
+            }
+        }
+        for file in &files.music {
+            if !self.am.in_loaded_group(file) {
+                self.am.remove_music(file);
             }
         }
     }
@@ -71,8 +412,8 @@ impl RenderSystem {
 #[macro_export]
 macro_rules! draw {
     ($rs: expr, $tex: ident, $src: expr, $dest: expr) => {
-        match $tex {
-            Some(tex) => $rs.draw(&tex, $src, $dest),
+        match &$tex {
+            Some(tex) => $rs.draw(tex, $src, $dest),
             None => (),
         }
     };
@@ -0,0 +1,162 @@
+use crate::bytes::{read_u16, read_u32, take};
+use crate::error::{Error, Result};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+
+// Pack file layout (all integers little endian):
+//   magic "RGEP"
+//   u32 entry count
+//   entries: u16 name length, name bytes, u32 offset, u32 size
+//   data: file contents, offsets are relative to the start of the data block
+const PACK_MAGIC: &[u8; 4] = b"RGEP";
+
+pub struct AssetPack {
+    entries: HashMap<String, Cow<'static, [u8]>>,
+}
+
+impl AssetPack {
+    pub fn new() -> Self {
+        AssetPack {
+            entries: HashMap::new(),
+        }
+    }
+
+    // Load a pack file from disk
+    pub fn open(file: &str) -> Result<Self> {
+        match fs::read(file) {
+            Ok(bytes) => match Self::parse(&bytes) {
+                Some(index) => Ok(AssetPack {
+                    entries: index
+                        .into_iter()
+                        .map(|(name, (start, end))| (name, Cow::Owned(bytes[start..end].to_vec())))
+                        .collect(),
+                }),
+                None => Err(Error::new(
+                    "AssetPack::open()",
+                    format!("Invalid pack file {}", file),
+                )),
+            },
+            Err(e) => Err(Error::new(
+                "AssetPack::open()",
+                format!("Unable to read {}: {}", file, e),
+            )),
+        }
+    }
+
+    // Use a pack that lives in memory for the whole program, e.g. from include_bytes!()
+    pub fn from_static(bytes: &'static [u8]) -> Result<Self> {
+        match Self::parse(bytes) {
+            Some(index) => Ok(AssetPack {
+                entries: index
+                    .into_iter()
+                    .map(|(name, (start, end))| (name, Cow::Borrowed(&bytes[start..end])))
+                    .collect(),
+            }),
+            None => Err(Error::new("AssetPack::from_static()", "Invalid pack data")),
+        }
+    }
+
+    // Build a pack from individually embedded files, see embed_assets!()
+    pub fn from_files(files: &[(&str, &'static [u8])]) -> Self {
+        AssetPack {
+            entries: files
+                .iter()
+                .map(|(name, bytes)| (name.to_string(), Cow::Borrowed(*bytes)))
+                .collect(),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, bytes: Vec<u8>) {
+        self.entries.insert(name.to_string(), Cow::Owned(bytes));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.entries.get(name).map(|bytes| bytes.as_ref())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|name| name.as_str())
+    }
+
+    // Write the pack to disk in the format read by AssetPack::open()
+    pub fn save(&self, file: &str) -> std::io::Result<()> {
+        let mut header = Vec::new();
+        let mut data = Vec::new();
+        header.extend_from_slice(PACK_MAGIC);
+        header.extend_from_slice(&to_u32(self.entries.len(), "entry count")?.to_le_bytes());
+        // Sorted so the same assets always give the same file
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|(name, _)| *name);
+        for (name, bytes) in entries {
+            let name_len = u16::try_from(name.len()).map_err(|_| {
+                std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Asset name too long: {}", name),
+                )
+            })?;
+            header.extend_from_slice(&name_len.to_le_bytes());
+            header.extend_from_slice(name.as_bytes());
+            header.extend_from_slice(&to_u32(data.len(), name)?.to_le_bytes());
+            header.extend_from_slice(&to_u32(bytes.len(), name)?.to_le_bytes());
+            data.extend_from_slice(bytes);
+        }
+        let mut f = fs::File::create(file)?;
+        f.write_all(&header)?;
+        f.write_all(&data)
+    }
+
+    // Returns the byte range of each entry within bytes
+    fn parse(bytes: &[u8]) -> Option<HashMap<String, (usize, usize)>> {
+        let mut pos = 0;
+        if take(bytes, &mut pos, PACK_MAGIC.len())? != PACK_MAGIC {
+            return None;
+        }
+        let count = read_u32(bytes, &mut pos)?;
+        let mut index = Vec::new();
+        for _ in 0..count {
+            let len = read_u16(bytes, &mut pos)? as usize;
+            let name = String::from_utf8(take(bytes, &mut pos, len)?.to_vec()).ok()?;
+            let offset = read_u32(bytes, &mut pos)? as usize;
+            let size = read_u32(bytes, &mut pos)? as usize;
+            index.push((name, offset, size));
+        }
+        let data_start = pos;
+        let mut entries = HashMap::new();
+        for (name, offset, size) in index {
+            let start = data_start.checked_add(offset)?;
+            let end = start.checked_add(size)?;
+            if end > bytes.len() {
+                return None;
+            }
+            entries.insert(name, (start, end));
+        }
+        Some(entries)
+    }
+}
+
+// Offsets and sizes are u32, packs can't grow past 4 GiB
+fn to_u32(n: usize, what: &str) -> std::io::Result<u32> {
+    u32::try_from(n).map_err(|_| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Pack too large at {}", what),
+        )
+    })
+}
+
+// Embed files into the binary, paths are relative to the crate root
+#[macro_export]
+macro_rules! embed_assets {
+    ($($file: literal),+ $(,)?) => {
+        $crate::asset_pack::AssetPack::from_files(&[
+            $(($file, &include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $file))[..])),*
+        ])
+    };
+}
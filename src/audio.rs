@@ -0,0 +1,484 @@
+use crate::error::{Error, Result};
+use crate::pointers::rw_from_bytes;
+use crate::sdl2;
+use crate::sdl2_mixer;
+
+use std::ffi::CString;
+use std::ptr::{self, NonNull};
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+pub const LOOP_FOREVER: i32 = -1;
+
+const MAX_VOLUME: f32 = sdl2::SDL_MIX_MAXVOLUME as f32;
+
+// Music last started by an AudioSystem, SDL_mixer can't tell which music is playing
+static CURRENT_MUSIC: AtomicPtr<sdl2_mixer::Mix_Music> = AtomicPtr::new(ptr::null_mut());
+
+fn to_mix_volume(volume: f32) -> i32 {
+    (volume.clamp(0.0, 1.0) * MAX_VOLUME).round() as i32
+}
+
+// Sound
+pub struct Sound {
+    chunk: NonNull<sdl2_mixer::Mix_Chunk>,
+    // Handed out as Weak to every SoundAccess, dropped with the chunk
+    alive: Rc<()>,
+}
+
+impl Sound {
+    pub fn new(file: &str) -> Result<Self> {
+        let cstr = CString::new(file)?;
+        let c_ptr = unsafe { sdl2_mixer::Mix_LoadWAV(cstr.as_ptr()) };
+        Sound::from_ptr(c_ptr, "Sound::new()")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let rw = rw_from_bytes(bytes, "Sound::from_bytes()")?;
+        let c_ptr = unsafe { sdl2_mixer::Mix_LoadWAV_RW(rw, 1) };
+        Sound::from_ptr(c_ptr, "Sound::from_bytes()")
+    }
+
+    fn from_ptr(c_ptr: *mut sdl2_mixer::Mix_Chunk, context: &'static str) -> Result<Self> {
+        match NonNull::new(c_ptr) {
+            Some(chunk) => Ok(Sound {
+                chunk,
+                alive: Rc::new(()),
+            }),
+            None => Err(Error::sdl(context)),
+        }
+    }
+
+    pub fn access(&self) -> SoundAccess {
+        SoundAccess {
+            chunk: self.chunk,
+            alive: Rc::downgrade(&self.alive),
+        }
+    }
+
+    // Playing on any channel, freeing the chunk would cut it off
+    pub fn playing(&self) -> bool {
+        let channels = unsafe { sdl2_mixer::Mix_AllocateChannels(-1) };
+        (0..channels).any(|c| unsafe {
+            sdl2_mixer::Mix_Playing(c) != 0 && sdl2_mixer::Mix_GetChunk(c) == self.chunk.as_ptr()
+        })
+    }
+}
+
+impl Drop for Sound {
+    fn drop(&mut self) {
+        unsafe { sdl2_mixer::Mix_FreeChunk(self.chunk.as_ptr()) }
+    }
+}
+
+// SoundAccess
+// A handle to a Sound owned elsewhere, e.g. by the AssetManager
+// Once the sound is freed the handle is invalid and plays nothing
+#[derive(Clone)]
+pub struct SoundAccess {
+    chunk: NonNull<sdl2_mixer::Mix_Chunk>,
+    alive: Weak<()>,
+}
+
+impl SoundAccess {
+    pub fn valid(&self) -> bool {
+        self.alive.strong_count() > 0
+    }
+
+    fn ptr(&self) -> Option<*mut sdl2_mixer::Mix_Chunk> {
+        if self.valid() {
+            Some(self.chunk.as_ptr())
+        } else {
+            None
+        }
+    }
+
+    // Volume of this sound relative to its channel, 0.0 - 1.0
+    pub fn set_volume(&self, volume: f32) {
+        if let Some(chunk) = self.ptr() {
+            unsafe {
+                sdl2_mixer::Mix_VolumeChunk(chunk, to_mix_volume(volume));
+            }
+        }
+    }
+}
+
+// Music
+pub struct Music {
+    mus: NonNull<sdl2_mixer::Mix_Music>,
+    // Music loaded from memory is streamed from this buffer
+    _data: Option<Vec<u8>>,
+    // Handed out as Weak to every MusicAccess, dropped with the music
+    alive: Rc<()>,
+}
+
+impl Music {
+    // Music is streamed from the file while it plays
+    pub fn new(file: &str) -> Result<Self> {
+        let cstr = CString::new(file)?;
+        let m_ptr = unsafe { sdl2_mixer::Mix_LoadMUS(cstr.as_ptr()) };
+        match NonNull::new(m_ptr) {
+            Some(mus) => Ok(Music {
+                mus,
+                _data: None,
+                alive: Rc::new(()),
+            }),
+            None => Err(Error::sdl("Music::new()")),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let data = bytes.to_vec();
+        let rw = rw_from_bytes(&data, "Music::from_bytes()")?;
+        let m_ptr = unsafe { sdl2_mixer::Mix_LoadMUS_RW(rw, 1) };
+        match NonNull::new(m_ptr) {
+            Some(mus) => Ok(Music {
+                mus,
+                _data: Some(data),
+                alive: Rc::new(()),
+            }),
+            None => Err(Error::sdl("Music::from_bytes()")),
+        }
+    }
+
+    pub fn access(&self) -> MusicAccess {
+        MusicAccess {
+            mus: self.mus,
+            alive: Rc::downgrade(&self.alive),
+        }
+    }
+
+    // Freeing playing music would stop it
+    pub fn playing(&self) -> bool {
+        let playing = unsafe { sdl2_mixer::Mix_PlayingMusic() != 0 };
+        playing && CURRENT_MUSIC.load(Ordering::Relaxed) == self.mus.as_ptr()
+    }
+}
+
+impl Drop for Music {
+    fn drop(&mut self) {
+        let _ = CURRENT_MUSIC.compare_exchange(
+            self.mus.as_ptr(),
+            ptr::null_mut(),
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        unsafe { sdl2_mixer::Mix_FreeMusic(self.mus.as_ptr()) }
+    }
+}
+
+// MusicAccess
+// Like SoundAccess, invalid once the music is freed
+#[derive(Clone)]
+pub struct MusicAccess {
+    mus: NonNull<sdl2_mixer::Mix_Music>,
+    alive: Weak<()>,
+}
+
+impl MusicAccess {
+    pub fn valid(&self) -> bool {
+        self.alive.strong_count() > 0
+    }
+
+    fn ptr(&self) -> Option<*mut sdl2_mixer::Mix_Music> {
+        if self.valid() {
+            Some(self.mus.as_ptr())
+        } else {
+            None
+        }
+    }
+}
+
+// AudioSystem
+pub struct AudioConfig {
+    pub frequency: i32,
+    pub stereo: bool,
+    pub chunk_size: i32,
+    // Audio driver to use, e.g. "dummy" to run without a sound device
+    pub driver: Option<&'static str>,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            frequency: sdl2_mixer::MIX_DEFAULT_FREQUENCY as i32,
+            stereo: true,
+            chunk_size: 2048,
+            driver: None,
+        }
+    }
+}
+
+// Handle to a set of mixer channels that share a volume
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ChannelGroup(i32);
+
+struct GroupInfo {
+    first: i32,
+    count: i32,
+    volume: f32,
+}
+
+pub struct AudioSystem {
+    groups: Vec<GroupInfo>,
+    num_channels: i32,
+    master_volume: f32,
+    music_volume: f32,
+}
+
+impl AudioSystem {
+    pub fn new(config: AudioConfig) -> Result<Self> {
+        unsafe {
+            if let Some(driver) = config.driver {
+                let cstr = CString::new(driver)?;
+                sdl2::SDL_QuitSubSystem(sdl2::SDL_INIT_AUDIO);
+                sdl2::SDL_SetHint(
+                    sdl2::SDL_HINT_AUDIODRIVER.as_ptr() as *const _,
+                    cstr.as_ptr(),
+                );
+            }
+            if sdl2::SDL_WasInit(sdl2::SDL_INIT_AUDIO) == 0
+                && sdl2::SDL_InitSubSystem(sdl2::SDL_INIT_AUDIO) != 0
+            {
+                return Err(Error::sdl("AudioSystem::new()"));
+            }
+            if sdl2_mixer::Mix_OpenAudio(
+                config.frequency,
+                sdl2::AUDIO_S16LSB as u16,
+                if config.stereo { 2 } else { 1 },
+                config.chunk_size,
+            ) != 0
+            {
+                return Err(Error::sdl("AudioSystem::new()"));
+            }
+            sdl2_mixer::Mix_AllocateChannels(0);
+        }
+        Ok(AudioSystem {
+            groups: Vec::new(),
+            num_channels: 0,
+            master_volume: 1.0,
+            music_volume: 1.0,
+        })
+    }
+
+    // Reserve channels for a new group, at most that many of its sounds play at once
+    pub fn add_group(&mut self, channels: i32) -> ChannelGroup {
+        let tag = self.groups.len() as i32;
+        let first = self.num_channels;
+        self.num_channels += channels.max(1);
+        unsafe {
+            sdl2_mixer::Mix_AllocateChannels(self.num_channels);
+            sdl2_mixer::Mix_GroupChannels(first, self.num_channels - 1, tag);
+        }
+        self.groups.push(GroupInfo {
+            first,
+            count: self.num_channels - first,
+            volume: 1.0,
+        });
+        let group = ChannelGroup(tag);
+        self.apply_group_volume(group);
+        group
+    }
+
+    // Volumes are 0.0 - 1.0
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+        for tag in 0..self.groups.len() {
+            self.apply_group_volume(ChannelGroup(tag as i32));
+        }
+        self.apply_music_volume();
+    }
+
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    pub fn set_group_volume(&mut self, group: ChannelGroup, volume: f32) {
+        match self.groups.get_mut(group.0 as usize) {
+            Some(info) => info.volume = volume.clamp(0.0, 1.0),
+            None => return,
+        }
+        self.apply_group_volume(group);
+    }
+
+    pub fn group_volume(&self, group: ChannelGroup) -> f32 {
+        self.groups
+            .get(group.0 as usize)
+            .map_or(0.0, |info| info.volume)
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.music_volume = volume.clamp(0.0, 1.0);
+        self.apply_music_volume();
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        self.music_volume
+    }
+
+    // Returns the channel the sound is playing on
+    // If all channels in the group are busy, the oldest sound is replaced
+    pub fn play(&self, sound: &SoundAccess, group: ChannelGroup, loops: i32) -> Option<i32> {
+        self.fade_in(sound, group, loops, 0)
+    }
+
+    pub fn fade_in(
+        &self,
+        sound: &SoundAccess,
+        group: ChannelGroup,
+        loops: i32,
+        ms: i32,
+    ) -> Option<i32> {
+        let chunk = match sound.ptr() {
+            Some(chunk) => chunk,
+            None => {
+                println!("AudioSystem::fade_in() - Sound has been freed");
+                return None;
+            }
+        };
+        let channel = self.free_channel(group)?;
+        let channel = unsafe {
+            sdl2_mixer::Mix_HaltChannel(channel);
+            sdl2_mixer::Mix_FadeInChannelTimed(channel, chunk, loops, ms, -1)
+        };
+        if channel < 0 {
+            println!(
+                "AudioSystem::fade_in() - {}",
+                Error::sdl("Mix_FadeInChannelTimed()")
+            );
+            None
+        } else {
+            Some(channel)
+        }
+    }
+
+    pub fn stop_channel(&self, channel: i32) {
+        unsafe {
+            sdl2_mixer::Mix_HaltChannel(channel);
+        }
+    }
+
+    pub fn fade_out_channel(&self, channel: i32, ms: i32) {
+        unsafe {
+            sdl2_mixer::Mix_FadeOutChannel(channel, ms);
+        }
+    }
+
+    pub fn stop_group(&self, group: ChannelGroup) {
+        unsafe {
+            sdl2_mixer::Mix_HaltGroup(group.0);
+        }
+    }
+
+    pub fn fade_out_group(&self, group: ChannelGroup, ms: i32) {
+        unsafe {
+            sdl2_mixer::Mix_FadeOutGroup(group.0, ms);
+        }
+    }
+
+    pub fn pause_group(&self, group: ChannelGroup) {
+        if let Some(info) = self.groups.get(group.0 as usize) {
+            for c in info.first..info.first + info.count {
+                unsafe { sdl2_mixer::Mix_Pause(c) }
+            }
+        }
+    }
+
+    pub fn resume_group(&self, group: ChannelGroup) {
+        if let Some(info) = self.groups.get(group.0 as usize) {
+            for c in info.first..info.first + info.count {
+                unsafe { sdl2_mixer::Mix_Resume(c) }
+            }
+        }
+    }
+
+    pub fn play_music(&self, music: &MusicAccess, loops: i32) {
+        self.fade_in_music(music, loops, 0);
+    }
+
+    pub fn fade_in_music(&self, music: &MusicAccess, loops: i32, ms: i32) {
+        let mus = match music.ptr() {
+            Some(mus) => mus,
+            None => {
+                println!("AudioSystem::fade_in_music() - Music has been freed");
+                return;
+            }
+        };
+        if unsafe { sdl2_mixer::Mix_FadeInMusic(mus, loops, ms) } != 0 {
+            println!(
+                "AudioSystem::fade_in_music() - {}",
+                Error::sdl("Mix_FadeInMusic()")
+            );
+        } else {
+            CURRENT_MUSIC.store(mus, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stop_music(&self) {
+        unsafe {
+            sdl2_mixer::Mix_HaltMusic();
+        }
+    }
+
+    pub fn fade_out_music(&self, ms: i32) {
+        unsafe {
+            sdl2_mixer::Mix_FadeOutMusic(ms);
+        }
+    }
+
+    pub fn pause_music(&self) {
+        unsafe { sdl2_mixer::Mix_PauseMusic() }
+    }
+
+    pub fn resume_music(&self) {
+        unsafe { sdl2_mixer::Mix_ResumeMusic() }
+    }
+
+    pub fn music_playing(&self) -> bool {
+        unsafe { sdl2_mixer::Mix_PlayingMusic() != 0 }
+    }
+
+    fn free_channel(&self, group: ChannelGroup) -> Option<i32> {
+        if group.0 < 0 || group.0 as usize >= self.groups.len() {
+            println!("AudioSystem::play() - No channel group {}", group.0);
+            return None;
+        }
+        let channel = unsafe { sdl2_mixer::Mix_GroupAvailable(group.0) };
+        if channel >= 0 {
+            return Some(channel);
+        }
+        let channel = unsafe { sdl2_mixer::Mix_GroupOldest(group.0) };
+        if channel >= 0 {
+            Some(channel)
+        } else {
+            None
+        }
+    }
+
+    fn apply_group_volume(&self, group: ChannelGroup) {
+        if let Some(info) = self.groups.get(group.0 as usize) {
+            let volume = to_mix_volume(info.volume * self.master_volume);
+            for c in info.first..info.first + info.count {
+                unsafe {
+                    sdl2_mixer::Mix_Volume(c, volume);
+                }
+            }
+        }
+    }
+
+    fn apply_music_volume(&self) {
+        unsafe {
+            sdl2_mixer::Mix_VolumeMusic(to_mix_volume(self.music_volume * self.master_volume));
+        }
+    }
+}
+
+impl Drop for AudioSystem {
+    fn drop(&mut self) {
+        unsafe {
+            sdl2_mixer::Mix_HaltChannel(-1);
+            sdl2_mixer::Mix_HaltMusic();
+            sdl2_mixer::Mix_CloseAudio();
+        }
+    }
+}
@@ -0,0 +1,33 @@
+// Little endian readers for the binary file formats
+// Each advances pos past what it read, None if bytes ends first
+
+pub(crate) fn take<'a>(bytes: &'a [u8], pos: &mut usize, n: usize) -> Option<&'a [u8]> {
+    let end = pos.checked_add(n)?;
+    let s = bytes.get(*pos..end)?;
+    *pos = end;
+    Some(s)
+}
+
+pub(crate) fn read_u8(bytes: &[u8], pos: &mut usize) -> Option<u8> {
+    take(bytes, pos, 1)?.first().copied()
+}
+
+pub(crate) fn read_u16(bytes: &[u8], pos: &mut usize) -> Option<u16> {
+    Some(u16::from_le_bytes(take(bytes, pos, 2)?.try_into().ok()?))
+}
+
+pub(crate) fn read_u32(bytes: &[u8], pos: &mut usize) -> Option<u32> {
+    Some(u32::from_le_bytes(take(bytes, pos, 4)?.try_into().ok()?))
+}
+
+pub(crate) fn read_i32(bytes: &[u8], pos: &mut usize) -> Option<i32> {
+    Some(i32::from_le_bytes(take(bytes, pos, 4)?.try_into().ok()?))
+}
+
+pub(crate) fn read_i64(bytes: &[u8], pos: &mut usize) -> Option<i64> {
+    Some(i64::from_le_bytes(take(bytes, pos, 8)?.try_into().ok()?))
+}
+
+pub(crate) fn read_f32(bytes: &[u8], pos: &mut usize) -> Option<f32> {
+    Some(f32::from_le_bytes(take(bytes, pos, 4)?.try_into().ok()?))
+}
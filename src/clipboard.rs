@@ -0,0 +1,33 @@
+use crate::error::{Error, Result};
+use crate::sdl2;
+
+use std::ffi::{CStr, CString};
+
+pub fn has_clipboard_text() -> bool {
+    unsafe { sdl2::SDL_HasClipboardText() == sdl2::SDL_bool::SDL_TRUE }
+}
+
+// None if the clipboard is empty or can't be read
+pub fn get_clipboard_text() -> Option<String> {
+    unsafe {
+        let text = sdl2::SDL_GetClipboardText();
+        if text.is_null() {
+            return None;
+        }
+        let s = CStr::from_ptr(text).to_string_lossy().to_string();
+        sdl2::SDL_free(text as *mut _);
+        if s.is_empty() {
+            None
+        } else {
+            Some(s)
+        }
+    }
+}
+
+pub fn set_clipboard_text(text: &str) -> Result<()> {
+    let cstr = CString::new(text)?;
+    if unsafe { sdl2::SDL_SetClipboardText(cstr.as_ptr()) } != 0 {
+        return Err(Error::sdl("set_clipboard_text()"));
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use crate::event::Event;
+use crate::sdl2;
+
+use std::collections::VecDeque;
+
+// Snapshots kept at most, older inputs can't be part of a combo anyway
+const MAX_HISTORY: usize = 64;
+
+// A timed key sequence, e.g. down, down + right, right, right + punch
+// A step matches when exactly its keys, out of all keys used by the combo, are held
+// Inputs that match no step are ignored as long as the timing still holds
+#[derive(Clone)]
+pub struct Combo {
+    pub name: String,
+    pub steps: Vec<Vec<sdl2::SDL_KeyCode>>,
+    // Milliseconds allowed between consecutive steps
+    pub max_gap: u32,
+    // Milliseconds allowed from the first to the last step, 0 is unlimited
+    pub max_total: u32,
+}
+
+impl Combo {
+    pub fn new(name: &str, max_gap: u32) -> Self {
+        Combo {
+            name: name.to_string(),
+            steps: Vec::new(),
+            max_gap,
+            max_total: 0,
+        }
+    }
+
+    pub fn step(mut self, keys: &[sdl2::SDL_KeyCode]) -> Self {
+        let mut keys = keys.to_vec();
+        keys.sort_by_key(|k| *k as i32);
+        keys.dedup();
+        self.steps.push(keys);
+        self
+    }
+
+    pub fn max_total(mut self, max_total: u32) -> Self {
+        self.max_total = max_total;
+        self
+    }
+
+    fn keys(&self) -> Vec<sdl2::SDL_KeyCode> {
+        let mut keys: Vec<sdl2::SDL_KeyCode> = self.steps.iter().flatten().copied().collect();
+        keys.sort_by_key(|k| *k as i32);
+        keys.dedup();
+        keys
+    }
+
+    // Longest time a match can span
+    fn window(&self) -> u32 {
+        let gaps = self.max_gap.saturating_mul(self.steps.len() as u32);
+        if self.max_total == 0 {
+            gaps
+        } else {
+            self.max_total.min(gaps)
+        }
+    }
+}
+
+// Keys held at a point in time, recorded whenever the held set changes
+struct Snapshot {
+    keys: Vec<sdl2::SDL_KeyCode>,
+    time: u32,
+}
+
+// ComboRecognizer
+// Call update() once per frame, then check completed()
+pub struct ComboRecognizer {
+    combos: Vec<Combo>,
+    // Every key used by a combo, sorted
+    keys: Vec<sdl2::SDL_KeyCode>,
+    history: VecDeque<Snapshot>,
+    time: u32,
+    completed: Vec<String>,
+}
+
+impl ComboRecognizer {
+    pub fn new() -> Self {
+        ComboRecognizer {
+            combos: Vec::new(),
+            keys: Vec::new(),
+            history: VecDeque::new(),
+            time: 0,
+            completed: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, combo: Combo) {
+        self.combos.push(combo);
+        self.update_keys();
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.combos.retain(|c| c.name != name);
+        self.update_keys();
+    }
+
+    fn update_keys(&mut self) {
+        self.keys = self.combos.iter().flat_map(|c| c.keys()).collect();
+        self.keys.sort_by_key(|k| *k as i32);
+        self.keys.dedup();
+    }
+
+    // Forget partial inputs, e.g. after a round ends
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.completed.clear();
+    }
+
+    pub fn update(&mut self, e: &Event) {
+        self.time += e.dt;
+        self.completed.clear();
+        // Keys pressed and released within one frame still count as held for that frame
+        let held: Vec<sdl2::SDL_KeyCode> = self
+            .keys
+            .iter()
+            .copied()
+            .filter(|k| e.get_key(*k).map_or(false, |b| b.held() || b.down()))
+            .collect();
+        if self.history.back().map_or(true, |s| s.keys != held) {
+            self.history.push_back(Snapshot {
+                keys: held,
+                time: self.time,
+            });
+        } else {
+            return;
+        }
+        let window = self.combos.iter().map(|c| c.window()).max().unwrap_or(0);
+        while self.history.len() > MAX_HISTORY
+            || self
+                .history
+                .front()
+                .map_or(false, |s| self.time - s.time > window)
+        {
+            self.history.pop_front();
+        }
+        for c in &self.combos {
+            if self.matches(c) {
+                self.completed.push(c.name.clone());
+            }
+        }
+    }
+
+    // Combos whose last step was entered this frame
+    pub fn completed(&self) -> &[String] {
+        &self.completed
+    }
+
+    pub fn just_completed(&self, name: &str) -> bool {
+        self.completed.iter().any(|c| c == name)
+    }
+
+    // Match the steps backwards, starting from this frame's snapshot
+    fn matches(&self, combo: &Combo) -> bool {
+        let keys = combo.keys();
+        let restrict = |s: &Snapshot| -> Vec<sdl2::SDL_KeyCode> {
+            s.keys
+                .iter()
+                .copied()
+                .filter(|k| keys.contains(k))
+                .collect()
+        };
+        let mut idx = self.history.len();
+        let mut later: Option<u32> = None;
+        for (i, step) in combo.steps.iter().rev().enumerate() {
+            let found = loop {
+                if idx == 0 {
+                    break None;
+                }
+                idx -= 1;
+                let s = &self.history[idx];
+                if let Some(t) = later {
+                    if t - s.time > combo.max_gap {
+                        break None;
+                    }
+                }
+                if restrict(s) == *step {
+                    break Some(s.time);
+                }
+                // The last step has to be this frame's input
+                if i == 0 {
+                    break None;
+                }
+            };
+            match found {
+                Some(t) => later = Some(t),
+                None => return false,
+            }
+        }
+        match later {
+            Some(first) => combo.max_total == 0 || self.time - first <= combo.max_total,
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::error::{Error, Result};
+use crate::event::Status;
+use crate::rect::PointF;
+use crate::sdl2;
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::ptr::NonNull;
+
+const AXIS_MAX: f32 = 32767.0;
+const DEFAULT_STICK_DEAD_ZONE: f32 = 0.2;
+const DEFAULT_TRIGGER_DEAD_ZONE: f32 = 0.05;
+
+// Load extra controller mappings, e.g. from gamecontrollerdb.txt
+// Returns the number of mappings added
+pub fn add_mappings_from_file(file: &str) -> Result<i32> {
+    let cfile = CString::new(file)?;
+    let n = unsafe {
+        let rw = sdl2::SDL_RWFromFile(cfile.as_ptr(), b"rb\0".as_ptr() as *const _);
+        if rw.is_null() {
+            return Err(Error::sdl("add_mappings_from_file()"));
+        }
+        sdl2::SDL_GameControllerAddMappingsFromRW(rw, 1)
+    };
+    if n < 0 {
+        return Err(Error::sdl("add_mappings_from_file()"));
+    }
+    Ok(n)
+}
+
+// Fraction of an axis' range that reads as 0, the rest is rescaled to 0 - 1
+#[derive(Copy, Clone)]
+pub struct DeadZones {
+    pub stick: f32,
+    pub trigger: f32,
+}
+
+impl Default for DeadZones {
+    fn default() -> Self {
+        DeadZones {
+            stick: DEFAULT_STICK_DEAD_ZONE,
+            trigger: DEFAULT_TRIGGER_DEAD_ZONE,
+        }
+    }
+}
+
+fn apply_dead_zone(v: f32, dead_zone: f32) -> f32 {
+    if v.abs() <= dead_zone || dead_zone >= 1.0 {
+        0.0
+    } else {
+        v.signum() * (v.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ControllerButton {
+    pub button: sdl2::SDL_GameControllerButton,
+    pub duration: u32,
+    pub(crate) status: u8,
+}
+
+impl ControllerButton {
+    fn new(button: sdl2::SDL_GameControllerButton) -> Self {
+        Self {
+            button,
+            duration: 0,
+            status: 0,
+        }
+    }
+
+    pub fn down(&self) -> bool {
+        self.status & Status::Down as u8 != 0
+    }
+
+    pub fn up(&self) -> bool {
+        self.status & Status::Up as u8 != 0
+    }
+
+    pub fn held(&self) -> bool {
+        self.status & Status::Held as u8 != 0
+    }
+
+    pub fn pressed(&self) -> bool {
+        self.status & Status::Pressed as u8 != 0
+    }
+}
+
+pub struct Controller {
+    // None without a device, e.g. for a ScriptedEventSource
+    c: Option<NonNull<sdl2::SDL_GameController>>,
+    pub instance_id: i32,
+    pub name: String,
+    pub dead_zones: DeadZones,
+    buttons: HashMap<sdl2::SDL_GameControllerButton, ControllerButton>,
+    // Raw values normalized to -1 - 1, triggers 0 - 1
+    axes: [f32; sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_MAX as usize],
+}
+
+impl Controller {
+    // Open the controller at a device index, as given by SDL_CONTROLLERDEVICEADDED
+    pub fn open(device_index: i32, dead_zones: DeadZones) -> Result<Self> {
+        let c_ptr = unsafe { sdl2::SDL_GameControllerOpen(device_index) };
+        let c = match NonNull::new(c_ptr) {
+            Some(c) => c,
+            None => return Err(Error::sdl("Controller::open()")),
+        };
+        let (instance_id, name) = unsafe {
+            let joystick = sdl2::SDL_GameControllerGetJoystick(c.as_ptr());
+            let name = sdl2::SDL_GameControllerName(c.as_ptr());
+            (
+                sdl2::SDL_JoystickInstanceID(joystick),
+                if name.is_null() {
+                    String::new()
+                } else {
+                    CStr::from_ptr(name).to_string_lossy().to_string()
+                },
+            )
+        };
+        Ok(Controller {
+            c: Some(c),
+            instance_id,
+            name,
+            dead_zones,
+            buttons: HashMap::new(),
+            axes: [0.0; sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_MAX as usize],
+        })
+    }
+
+    // A controller without a device, its state only changes through events
+    pub(crate) fn detached(instance_id: i32, name: &str, dead_zones: DeadZones) -> Self {
+        Controller {
+            c: None,
+            instance_id,
+            name: name.to_string(),
+            dead_zones,
+            buttons: HashMap::new(),
+            axes: [0.0; sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_MAX as usize],
+        }
+    }
+
+    pub(crate) fn begin_update(&mut self, ts: u32) {
+        for (_, b) in &mut self.buttons {
+            if b.held() {
+                b.duration += ts;
+            }
+            // Reset pressed/released
+            b.status &= Status::Held as u8;
+        }
+    }
+
+    pub(crate) fn button_down(&mut self, button: sdl2::SDL_GameControllerButton) {
+        let b = self.get_button_mut(button);
+        b.status = Status::Down as u8 | Status::Held as u8 | Status::Pressed as u8;
+        b.duration = 0;
+    }
+
+    pub(crate) fn button_up(&mut self, button: sdl2::SDL_GameControllerButton) {
+        let b = self.get_button_mut(button);
+        b.status = Status::Up as u8;
+    }
+
+    pub(crate) fn set_axis(&mut self, axis: sdl2::SDL_GameControllerAxis, value: i16) {
+        if let Some(a) = self.axes.get_mut(axis as usize) {
+            *a = (value as f32 / AXIS_MAX).clamp(-1.0, 1.0);
+        }
+    }
+
+    // State for input recordings
+    pub(crate) fn buttons(&self) -> impl Iterator<Item = &ControllerButton> {
+        self.buttons.values()
+    }
+
+    pub(crate) fn raw_axes(&self) -> &[f32] {
+        &self.axes
+    }
+
+    pub(crate) fn set_button(&mut self, b: ControllerButton) {
+        self.buttons.insert(b.button, b);
+    }
+
+    pub(crate) fn set_raw_axis(&mut self, index: usize, value: f32) {
+        if let Some(a) = self.axes.get_mut(index) {
+            *a = value;
+        }
+    }
+
+    fn get_button_mut(&mut self, button: sdl2::SDL_GameControllerButton) -> &mut ControllerButton {
+        self.buttons
+            .entry(button)
+            .or_insert_with(|| ControllerButton::new(button))
+    }
+
+    pub fn get_button(&self, button: sdl2::SDL_GameControllerButton) -> Option<&ControllerButton> {
+        self.buttons.get(&button)
+    }
+
+    // Axis value without dead zones
+    pub fn raw_axis(&self, axis: sdl2::SDL_GameControllerAxis) -> f32 {
+        self.axes.get(axis as usize).copied().unwrap_or(0.0)
+    }
+
+    // Axis value with the stick or trigger dead zone applied
+    pub fn axis(&self, axis: sdl2::SDL_GameControllerAxis) -> f32 {
+        match axis {
+            sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_TRIGGERLEFT
+            | sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_TRIGGERRIGHT => {
+                apply_dead_zone(self.raw_axis(axis), self.dead_zones.trigger)
+            }
+            _ => apply_dead_zone(self.raw_axis(axis), self.dead_zones.stick),
+        }
+    }
+
+    pub fn left_stick(&self) -> PointF {
+        self.stick(
+            sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTX,
+            sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTY,
+        )
+    }
+
+    pub fn right_stick(&self) -> PointF {
+        self.stick(
+            sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_RIGHTX,
+            sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_RIGHTY,
+        )
+    }
+
+    pub fn left_trigger(&self) -> f32 {
+        self.axis(sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_TRIGGERLEFT)
+    }
+
+    pub fn right_trigger(&self) -> f32 {
+        self.axis(sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_TRIGGERRIGHT)
+    }
+
+    // Radial dead zone so diagonals aren't cut off
+    fn stick(&self, x: sdl2::SDL_GameControllerAxis, y: sdl2::SDL_GameControllerAxis) -> PointF {
+        let (x, y) = (self.raw_axis(x), self.raw_axis(y));
+        let len = (x * x + y * y).sqrt();
+        if len == 0.0 {
+            return PointF { x: 0.0, y: 0.0 };
+        }
+        let scale = apply_dead_zone(len.min(1.0), self.dead_zones.stick) / len;
+        PointF {
+            x: x * scale,
+            y: y * scale,
+        }
+    }
+}
+
+impl Drop for Controller {
+    fn drop(&mut self) {
+        if let Some(c) = self.c {
+            unsafe { sdl2::SDL_GameControllerClose(c.as_ptr()) }
+        }
+    }
+}
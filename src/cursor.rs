@@ -0,0 +1,148 @@
+use crate::error::{Error, Result};
+use crate::pointers::{to_sdl_bool, Surface};
+use crate::sdl2;
+
+use std::collections::HashMap;
+use std::ptr::NonNull;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SystemCursor {
+    Arrow,
+    IBeam,
+    Wait,
+    Crosshair,
+    WaitArrow,
+    SizeNWSE,
+    SizeNESW,
+    SizeWE,
+    SizeNS,
+    SizeAll,
+    No,
+    Hand,
+}
+
+impl SystemCursor {
+    fn to_sdl(&self) -> sdl2::SDL_SystemCursor {
+        match self {
+            SystemCursor::Arrow => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_ARROW,
+            SystemCursor::IBeam => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_IBEAM,
+            SystemCursor::Wait => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_WAIT,
+            SystemCursor::Crosshair => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_CROSSHAIR,
+            SystemCursor::WaitArrow => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_WAITARROW,
+            SystemCursor::SizeNWSE => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_SIZENWSE,
+            SystemCursor::SizeNESW => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_SIZENESW,
+            SystemCursor::SizeWE => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_SIZEWE,
+            SystemCursor::SizeNS => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_SIZENS,
+            SystemCursor::SizeAll => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_SIZEALL,
+            SystemCursor::No => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_NO,
+            SystemCursor::Hand => sdl2::SDL_SystemCursor::SDL_SYSTEM_CURSOR_HAND,
+        }
+    }
+}
+
+// Cursor
+pub struct Cursor {
+    c: NonNull<sdl2::SDL_Cursor>,
+}
+
+impl Cursor {
+    pub fn system(cursor: SystemCursor) -> Result<Self> {
+        let c_ptr = unsafe { sdl2::SDL_CreateSystemCursor(cursor.to_sdl()) };
+        match NonNull::new(c_ptr) {
+            Some(c) => Ok(Cursor { c }),
+            None => Err(Error::sdl("Cursor::system()")),
+        }
+    }
+
+    // The hotspot is the pixel of the image that points at the mouse position
+    pub fn from_file(file: &str, hot_x: i32, hot_y: i32) -> Result<Self> {
+        let surf = Surface::new(file)?;
+        let c_ptr = unsafe { sdl2::SDL_CreateColorCursor(surf.s.as_ptr(), hot_x, hot_y) };
+        match NonNull::new(c_ptr) {
+            Some(c) => Ok(Cursor { c }),
+            None => Err(Error::sdl("Cursor::from_file()")),
+        }
+    }
+
+    // The cursor must not be dropped while it is active
+    pub fn set(&self) {
+        unsafe { sdl2::SDL_SetCursor(self.c.as_ptr()) }
+    }
+}
+
+impl Drop for Cursor {
+    fn drop(&mut self) {
+        unsafe { sdl2::SDL_FreeCursor(self.c.as_ptr()) }
+    }
+}
+
+// CursorManager
+// Owns every cursor that has been used so the active one is never freed
+pub struct CursorManager {
+    system: HashMap<SystemCursor, Cursor>,
+    // By file and hotspot
+    custom: HashMap<(String, i32, i32), Cursor>,
+}
+
+impl CursorManager {
+    pub fn new() -> Self {
+        CursorManager {
+            system: HashMap::new(),
+            custom: HashMap::new(),
+        }
+    }
+
+    pub fn set_system(&mut self, cursor: SystemCursor) -> Result<()> {
+        if !self.system.contains_key(&cursor) {
+            self.system.insert(cursor, Cursor::system(cursor)?);
+        }
+        if let Some(c) = self.system.get(&cursor) {
+            c.set();
+        }
+        Ok(())
+    }
+
+    pub fn set_custom(&mut self, file: &str, hot_x: i32, hot_y: i32) -> Result<()> {
+        let key = (file.to_string(), hot_x, hot_y);
+        if !self.custom.contains_key(&key) {
+            let c = Cursor::from_file(file, hot_x, hot_y)?;
+            self.custom.insert(key.clone(), c);
+        }
+        if let Some(c) = self.custom.get(&key) {
+            c.set();
+        }
+        Ok(())
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        self.set_system(SystemCursor::Arrow)
+    }
+}
+
+pub fn show_cursor(show: bool) {
+    let toggle = if show {
+        sdl2::SDL_ENABLE
+    } else {
+        sdl2::SDL_DISABLE
+    };
+    unsafe {
+        sdl2::SDL_ShowCursor(toggle as i32);
+    }
+}
+
+pub fn cursor_visible() -> bool {
+    unsafe { sdl2::SDL_ShowCursor(sdl2::SDL_QUERY as i32) == sdl2::SDL_ENABLE as i32 }
+}
+
+// Hides the cursor and reports only relative motion in Event::mouse_delta,
+// the cursor position doesn't change while enabled
+pub fn set_relative_mouse_mode(enabled: bool) -> Result<()> {
+    if unsafe { sdl2::SDL_SetRelativeMouseMode(to_sdl_bool(enabled)) } != 0 {
+        return Err(Error::sdl("set_relative_mouse_mode()"));
+    }
+    Ok(())
+}
+
+pub fn relative_mouse_mode() -> bool {
+    unsafe { sdl2::SDL_GetRelativeMouseMode() == sdl2::SDL_bool::SDL_TRUE }
+}
@@ -0,0 +1,116 @@
+use crate::error::{Error, Result};
+use crate::rect::Rect;
+use crate::sdl2;
+
+use std::ffi::CStr;
+use std::mem;
+
+// DPI that corresponds to a scale of 1.0
+pub const DEFAULT_DPI: f32 = 96.0;
+
+#[derive(Copy, Clone, Debug)]
+pub struct DisplayMode {
+    // SDL_PixelFormatEnum value
+    pub format: u32,
+    pub w: i32,
+    pub h: i32,
+    // 0 if unknown
+    pub refresh_rate: i32,
+}
+
+impl DisplayMode {
+    fn from_sdl(mode: &sdl2::SDL_DisplayMode) -> Self {
+        DisplayMode {
+            format: mode.format,
+            w: mode.w,
+            h: mode.h,
+            refresh_rate: mode.refresh_rate,
+        }
+    }
+}
+
+pub struct Display {
+    pub index: i32,
+    pub name: String,
+    // Position and size in the global desktop coordinates
+    pub bounds: Rect,
+    // Bounds minus taskbars, docks and menu bars
+    pub usable_bounds: Rect,
+    pub ddpi: f32,
+    pub hdpi: f32,
+    pub vdpi: f32,
+    pub desktop_mode: DisplayMode,
+    pub current_mode: DisplayMode,
+    pub modes: Vec<DisplayMode>,
+}
+
+impl Display {
+    pub fn count() -> i32 {
+        unsafe { sdl2::SDL_GetNumVideoDisplays() }.max(0)
+    }
+
+    pub fn all() -> Result<Vec<Display>> {
+        (0..Self::count()).map(Self::get).collect()
+    }
+
+    pub fn get(index: i32) -> Result<Self> {
+        unsafe {
+            let name = sdl2::SDL_GetDisplayName(index);
+            if name.is_null() {
+                return Err(Error::sdl("Display::get()"));
+            }
+            let name = CStr::from_ptr(name).to_string_lossy().to_string();
+
+            let mut bounds = mem::zeroed();
+            if sdl2::SDL_GetDisplayBounds(index, &mut bounds) != 0 {
+                return Err(Error::sdl("Display::get()"));
+            }
+            let mut usable_bounds = mem::zeroed();
+            if sdl2::SDL_GetDisplayUsableBounds(index, &mut usable_bounds) != 0 {
+                usable_bounds = bounds;
+            }
+
+            // DPI isn't available on every platform
+            let (mut ddpi, mut hdpi, mut vdpi) = (DEFAULT_DPI, DEFAULT_DPI, DEFAULT_DPI);
+            if sdl2::SDL_GetDisplayDPI(index, &mut ddpi, &mut hdpi, &mut vdpi) != 0 {
+                (ddpi, hdpi, vdpi) = (DEFAULT_DPI, DEFAULT_DPI, DEFAULT_DPI);
+            }
+
+            let mut mode = mem::zeroed();
+            if sdl2::SDL_GetDesktopDisplayMode(index, &mut mode) != 0 {
+                return Err(Error::sdl("Display::get()"));
+            }
+            let desktop_mode = DisplayMode::from_sdl(&mode);
+            let current_mode = if sdl2::SDL_GetCurrentDisplayMode(index, &mut mode) == 0 {
+                DisplayMode::from_sdl(&mode)
+            } else {
+                desktop_mode
+            };
+
+            let mut modes = Vec::new();
+            for i in 0..sdl2::SDL_GetNumDisplayModes(index).max(0) {
+                if sdl2::SDL_GetDisplayMode(index, i, &mut mode) == 0 {
+                    modes.push(DisplayMode::from_sdl(&mode));
+                }
+            }
+
+            Ok(Display {
+                index,
+                name,
+                bounds: Rect::from_sdl_rect(bounds),
+                usable_bounds: Rect::from_sdl_rect(usable_bounds),
+                ddpi,
+                hdpi,
+                vdpi,
+                desktop_mode,
+                current_mode,
+                modes,
+            })
+        }
+    }
+
+    // UI scale suggested by the display DPI
+    pub fn scale(&self) -> f32 {
+        self.ddpi / DEFAULT_DPI
+    }
+}
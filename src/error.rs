@@ -0,0 +1,43 @@
+use crate::sdl2;
+
+use std::ffi::CStr;
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub context: &'static str,
+    pub msg: String,
+}
+
+impl Error {
+    pub fn new(context: &'static str, msg: impl Into<String>) -> Self {
+        Error {
+            context,
+            msg: msg.into(),
+        }
+    }
+
+    // Capture the last SDL error, IMG_GetError() and Mix_GetError() are aliases of SDL_GetError()
+    pub fn sdl(context: &'static str) -> Self {
+        let msg = unsafe { CStr::from_ptr(sdl2::SDL_GetError()) }
+            .to_string_lossy()
+            .to_string();
+        Error::new(context, msg)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.context, self.msg)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::ffi::NulError> for Error {
+    fn from(e: std::ffi::NulError) -> Self {
+        Error::new("CString::new()", e.to_string())
+    }
+}
@@ -1,21 +1,38 @@
 use num_traits::FromPrimitive;
 
+use crate::controller::{Controller, DeadZones};
+use crate::event_source::{EventSource, SdlEventSource};
+use crate::file_drop::FileDrop;
+use crate::keyboard::{Chord, Mod};
+use crate::pointers::pixel_scale;
 use crate::rect::*;
 use crate::sdl2;
-use std::{collections::HashMap, mem};
+use crate::touch::{touch_to_world, Finger, Gesture};
+use std::collections::HashMap;
 
-const NUM_MICE: u8 = 3;
+pub(crate) const NUM_MICE: u8 = 5;
+// Distance the mouse may move while held and still click, further starts a drag
 const MAX_CLICK_DIFF: f32 = 10.0;
 const MOUSE_BTN_LEFT: u8 = sdl2::SDL_BUTTON_LEFT as u8;
 const MOUSE_BTN_RIGHT: u8 = sdl2::SDL_BUTTON_RIGHT as u8;
 const MOUSE_BTN_MIDDLE: u8 = sdl2::SDL_BUTTON_MIDDLE as u8;
+const MOUSE_BTN_X1: u8 = sdl2::SDL_BUTTON_X1 as u8;
+const MOUSE_BTN_X2: u8 = sdl2::SDL_BUTTON_X2 as u8;
+// Ctrl on most platforms, Cmd on macOS
+const SHORTCUT_MODS: u16 = sdl2::SDL_Keymod::KMOD_CTRL as u16 | sdl2::SDL_Keymod::KMOD_GUI as u16;
+// Ctrl on most platforms, Alt (Option) on macOS
+const WORD_MODS: u16 = sdl2::SDL_Keymod::KMOD_CTRL as u16 | sdl2::SDL_Keymod::KMOD_ALT as u16;
+const SELECT_MODS: u16 = sdl2::SDL_Keymod::KMOD_SHIFT as u16;
 
 #[repr(u8)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Mouse {
     Left = 0,
     Right,
     Middle,
+    // Side buttons, usually back and forward
+    X1,
+    X2,
 }
 
 impl Mouse {
@@ -24,6 +41,8 @@ impl Mouse {
             MOUSE_BTN_LEFT => Some(Mouse::Left),
             MOUSE_BTN_RIGHT => Some(Mouse::Right),
             MOUSE_BTN_MIDDLE => Some(Mouse::Middle),
+            MOUSE_BTN_X1 => Some(Mouse::X1),
+            MOUSE_BTN_X2 => Some(Mouse::X2),
             _ => None,
         }
     }
@@ -36,21 +55,34 @@ pub enum Status {
     Up = 0x02,
     Held = 0x04,
     Pressed = 0x08,
+    // Mouse buttons only
+    DragStart = 0x10,
+    Dragging = 0x20,
+    DragEnd = 0x40,
+    // Keys only, set by OS auto-repeat
+    Repeat = 0x80,
 }
 
 #[derive(Copy, Clone)]
 pub struct MouseButton {
     pub mouse: Mouse,
+    // Also the drag origin
     pub click_pos: Point,
+    // Mouse position during the last drag
+    pub drag_pos: Point,
+    // Clicks in quick succession that led to the last press, 2 for a double click
+    pub clicks: u8,
     pub duration: u32,
-    status: u8,
+    pub(crate) status: u8,
 }
 
 impl MouseButton {
-    fn new(mouse: Mouse) -> Self {
+    pub(crate) fn new(mouse: Mouse) -> Self {
         Self {
             mouse,
             click_pos: Point { x: 0, y: 0 },
+            drag_pos: Point { x: 0, y: 0 },
+            clicks: 0,
             duration: 0,
             status: 0,
         }
@@ -71,24 +103,78 @@ impl MouseButton {
     pub fn clicked(&self) -> bool {
         self.status & Status::Pressed as u8 != 0
     }
+
+    // Pressed down as the second click of a double click
+    pub fn double_clicked(&self) -> bool {
+        self.down() && self.clicks == 2
+    }
+
+    pub fn triple_clicked(&self) -> bool {
+        self.down() && self.clicks == 3
+    }
+
+    pub fn drag_started(&self) -> bool {
+        self.status & Status::DragStart as u8 != 0
+    }
+
+    pub fn dragging(&self) -> bool {
+        self.status & Status::Dragging as u8 != 0
+    }
+
+    // Released after dragging, clicked() is false then
+    pub fn drag_ended(&self) -> bool {
+        self.status & Status::DragEnd as u8 != 0
+    }
+
+    pub fn drag_delta(&self) -> Point {
+        Point {
+            x: self.drag_pos.x - self.click_pos.x,
+            y: self.drag_pos.y - self.click_pos.y,
+        }
+    }
+
+    pub fn drag_distance(&self) -> f32 {
+        self.click_pos.dist(&self.drag_pos)
+    }
 }
 
 #[derive(Copy, Clone)]
 pub struct KeyButton {
     pub key: sdl2::SDL_KeyCode,
+    // Physical key, the same on every layout
+    pub scancode: sdl2::SDL_Scancode,
     pub duration: u32,
-    status: u8,
+    pub(crate) status: u8,
 }
 
 impl KeyButton {
-    fn new(key: sdl2::SDL_KeyCode) -> Self {
+    pub(crate) fn new(key: sdl2::SDL_KeyCode, scancode: sdl2::SDL_Scancode) -> Self {
         Self {
             key: key,
+            scancode,
             duration: 0,
             status: 0,
         }
     }
 
+    // Repeats are pressed again but not down
+    fn press(&mut self, repeat: bool) {
+        let held = self.held();
+        self.status =
+            (self.status & Status::Down as u8) | Status::Pressed as u8 | Status::Held as u8;
+        if repeat {
+            self.status |= Status::Repeat as u8;
+        }
+        if !held {
+            self.status |= Status::Down as u8;
+            self.duration = 0;
+        }
+    }
+
+    fn release(&mut self) {
+        self.status = Status::Up as u8;
+    }
+
     pub fn down(&self) -> bool {
         self.status & Status::Down as u8 != 0
     }
@@ -104,6 +190,12 @@ impl KeyButton {
     pub fn pressed(&self) -> bool {
         self.status & Status::Pressed as u8 != 0
     }
+
+    // Pressed by OS auto-repeat this frame, the rate depends on the OS
+    // See KeyRepeat for a repeat that is the same everywhere
+    pub fn repeated(&self) -> bool {
+        self.status & Status::Repeat as u8 != 0
+    }
 }
 
 #[repr(u8)]
@@ -114,140 +206,450 @@ pub enum InputSeek {
     End,
 }
 
+// Unit of abs_mouse, mouse_delta and window dimensions
+// Window units and pixels differ on high-DPI displays
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CoordUnit {
+    Window,
+    Pixels,
+}
+
 pub struct Event {
+    // Window whose events this receives, 0 receives events from every window
+    pub window_id: u32,
+    camera: Rect,
+    screen: Dimensions,
+    coord_unit: CoordUnit,
+    pixel_scale: DimensionsF,
+    // The coordinate unit changed, re-read the pixel scale in the next update
+    pixel_scale_stale: bool,
     pub dt: u32,
     pub quit: bool,
     pub resized: bool,
+    // Window events this frame
+    pub focus_gained: bool,
+    pub focus_lost: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub restored: bool,
+    pub mouse_entered: bool,
+    pub mouse_left: bool,
+    pub moved: bool,
+    pub close_requested: bool,
+    // Window state
+    pub has_focus: bool,
+    pub is_minimized: bool,
+    pub has_mouse: bool,
+    pub window_pos: Point,
     pub old_dim: Dimensions,
     pub new_dim: Dimensions,
     pub mouse: Point,
     pub abs_mouse: Point,
+    // Motion this frame, summed over every motion event
     pub mouse_delta: Point,
+    // Relative mouse mode, abs_mouse doesn't move and mouse_delta has all motion
+    pub relative_mouse: bool,
+    text_input_active: bool,
+    // Wheel movement this frame, positive y scrolls towards the user and positive x right
+    // Flipped (natural) scrolling is undone so the direction is the same on every system
     pub scroll: i32,
+    pub scroll_x: i32,
+    // Fractional wheel movement from trackpads and smooth wheels, same directions as scroll
+    pub scroll_precise: PointF,
+    // Mouse position at the last wheel event, e.g. to zoom towards the cursor
+    pub scroll_mouse: Point,
+    pub scroll_abs_mouse: Point,
     pub input_text: String,
     pub input_backspace: i32,
     pub input_delete: i32,
     pub input_move: i32,
+    // Cursor movement by whole words
+    pub input_word_move: i32,
     pub input_seek: InputSeek,
+    // Shift was held during the last cursor movement
+    pub input_select: bool,
+    // Clipboard shortcuts, pasted text is also appended to input_text
+    pub input_copy: bool,
+    pub input_cut: bool,
+    pub input_paste: bool,
+    pub input_select_all: bool,
+    pub input_undo: bool,
+    pub input_redo: bool,
+    // IME composition that hasn't been committed to input_text yet, kept between frames
+    pub input_composition: String,
+    // Cursor and selection length within input_composition, in chars
+    pub input_composition_cursor: i32,
+    pub input_composition_len: i32,
     pub mouse_buttons: [MouseButton; NUM_MICE as usize],
     pub key_buttons: HashMap<sdl2::SDL_KeyCode, KeyButton>,
+    // The same keys by physical position, e.g. WASD on any layout
+    pub scan_buttons: HashMap<sdl2::SDL_Scancode, KeyButton>,
+    // SDL_Keymod bits as of the last key event
+    pub mods: u16,
+    // Game controllers by joystick instance id
+    pub controllers: HashMap<i32, Controller>,
+    pub controllers_added: Vec<i32>,
+    pub controllers_removed: Vec<i32>,
+    // Dead zones given to newly connected controllers
+    pub controller_dead_zones: DeadZones,
+    // Touch fingers in the order they went down, lifted fingers stay for one frame
+    pub fingers: Vec<Finger>,
+    pub gesture: Gesture,
+    // Files and text dropped onto the window
+    pub file_drop: FileDrop,
 }
 
 impl Event {
     pub fn new() -> Self {
+        Self::for_window(0)
+    }
+
+    pub fn for_window(window_id: u32) -> Self {
         Self {
+            window_id,
+            camera: Rect::new(),
+            screen: Dimensions { w: 0, h: 0 },
+            coord_unit: CoordUnit::Window,
+            pixel_scale: DimensionsF { w: 1.0, h: 1.0 },
+            pixel_scale_stale: false,
             dt: 0,
             quit: false,
             resized: false,
+            focus_gained: false,
+            focus_lost: false,
+            minimized: false,
+            maximized: false,
+            restored: false,
+            mouse_entered: false,
+            mouse_left: false,
+            moved: false,
+            close_requested: false,
+            has_focus: true,
+            is_minimized: false,
+            has_mouse: false,
+            window_pos: Point { x: 0, y: 0 },
             old_dim: Dimensions { w: 0, h: 0 },
             new_dim: Dimensions { w: 0, h: 0 },
             mouse: Point { x: 0, y: 0 },
             abs_mouse: Point { x: 0, y: 0 },
             mouse_delta: Point { x: 0, y: 0 },
+            relative_mouse: false,
+            text_input_active: false,
             scroll: 0,
+            scroll_x: 0,
+            scroll_precise: PointF { x: 0.0, y: 0.0 },
+            scroll_mouse: Point { x: 0, y: 0 },
+            scroll_abs_mouse: Point { x: 0, y: 0 },
             input_text: "".to_string(),
             input_backspace: 0,
             input_delete: 0,
             input_move: 0,
+            input_word_move: 0,
             input_seek: InputSeek::None,
+            input_select: false,
+            input_copy: false,
+            input_cut: false,
+            input_paste: false,
+            input_select_all: false,
+            input_undo: false,
+            input_redo: false,
+            input_composition: String::new(),
+            input_composition_cursor: 0,
+            input_composition_len: 0,
             mouse_buttons: [
                 MouseButton::new(Mouse::Left),
                 MouseButton::new(Mouse::Right),
                 MouseButton::new(Mouse::Middle),
+                MouseButton::new(Mouse::X1),
+                MouseButton::new(Mouse::X2),
             ],
             key_buttons: HashMap::new(),
+            scan_buttons: HashMap::new(),
+            mods: 0,
+            controllers: HashMap::new(),
+            controllers_added: Vec::new(),
+            controllers_removed: Vec::new(),
+            controller_dead_zones: DeadZones::default(),
+            fingers: Vec::new(),
+            gesture: Gesture::new(),
+            file_drop: FileDrop::new(),
         }
     }
 
+    // Drains the whole SDL queue, an Event for one window must be updated by EventRouter
+    // so the other windows' events aren't lost
     pub fn update(&mut self, ts: u32, camera: &Rect, screen: &Dimensions) {
+        debug_assert!(
+            self.window_id == 0,
+            "Event::update() on the Event of window {}, use EventRouter",
+            self.window_id
+        );
+        self.update_from(&mut SdlEventSource::new(), ts, camera, screen);
+    }
+
+    // Read input from somewhere other than SDL, e.g. a ScriptedEventSource
+    pub fn update_from(
+        &mut self,
+        src: &mut dyn EventSource,
+        ts: u32,
+        camera: &Rect,
+        screen: &Dimensions,
+    ) {
+        self.set_view(camera, screen);
+        src.begin_frame();
+        self.begin_update(ts, src);
+        // Handle events
+        while let Some(event) = src.poll() {
+            if self.accepts(&event) {
+                self.update_event(&event, src);
+            }
+        }
+    }
+
+    // screen must be given in the current coordinate unit
+    // The pixel scale is re-read from the window in the next update
+    pub fn set_coord_unit(&mut self, unit: CoordUnit) {
+        self.coord_unit = unit;
+        self.pixel_scale_stale = self.window_id != 0;
+    }
+
+    pub fn coord_unit(&self) -> CoordUnit {
+        self.coord_unit
+    }
+
+    // Pixels per window unit of the window that last reported its size
+    pub fn pixel_scale(&self) -> DimensionsF {
+        self.pixel_scale
+    }
+
+    // Camera and screen used to map window coordinates to world coordinates
+    pub fn set_view(&mut self, camera: &Rect, screen: &Dimensions) {
+        self.camera = *camera;
+        self.screen = *screen;
+    }
+
+    pub fn accepts(&self, event: &sdl2::SDL_Event) -> bool {
+        self.window_id == 0 || event_window_id(event).map_or(true, |id| id == self.window_id)
+    }
+
+    pub(crate) fn begin_update(&mut self, ts: u32, src: &dyn EventSource) {
         self.dt = ts;
         // Reset event flags
         self.quit = false;
         self.resized = false;
+        self.focus_gained = false;
+        self.focus_lost = false;
+        self.minimized = false;
+        self.maximized = false;
+        self.restored = false;
+        self.mouse_entered = false;
+        self.mouse_left = false;
+        self.moved = false;
+        self.close_requested = false;
         // Reset text editing
         self.input_text.clear();
         self.input_backspace = 0;
         self.input_delete = 0;
         self.input_move = 0;
+        self.input_word_move = 0;
         self.input_seek = InputSeek::None;
-        // Update mouse
-        let (mut x, mut y) = (0, 0);
-        unsafe {
-            sdl2::SDL_GetMouseState(&mut x, &mut y);
+        self.input_select = false;
+        self.input_copy = false;
+        self.input_cut = false;
+        self.input_paste = false;
+        self.input_select_all = false;
+        self.input_undo = false;
+        self.input_redo = false;
+        self.text_input_active = src.text_input_active();
+        if self.pixel_scale_stale {
+            self.pixel_scale_stale = false;
+            self.update_pixel_scale(self.window_id, src);
         }
-        self.abs_mouse = Point { x, y };
-        self.mouse = Point {
-            x: (x as f32 * camera.w() / screen.w as f32 + camera.x()) as i32,
-            y: (y as f32 * camera.h() / screen.h as f32 + camera.y()) as i32,
-        };
+        // Update mouse, the position is only known while the mouse is over this window
+        if let Some(p) = src.mouse_state(self.window_id) {
+            self.abs_mouse = self.to_unit(p);
+        }
+        self.mouse = self.to_world(self.abs_mouse);
         // Reset mouse movement
         self.mouse_delta = Point { x: 0, y: 0 };
+        self.relative_mouse = src.relative_mouse_mode();
         self.scroll = 0;
+        self.scroll_x = 0;
+        self.scroll_precise = PointF { x: 0.0, y: 0.0 };
         // Update mouse buttons
         for b in &mut self.mouse_buttons {
+            // Reset pressed/released and drag start/end
+            b.status &= Status::Held as u8 | Status::Dragging as u8;
             if b.held() {
                 b.duration += ts;
             }
-            // Reset pressed/released
-            b.status &= Status::Held as u8;
         }
+        self.update_drags();
         // Update keys
-        for (_, b) in &mut self.key_buttons {
+        for b in self
+            .key_buttons
+            .values_mut()
+            .chain(self.scan_buttons.values_mut())
+        {
             if b.held() {
                 b.duration += ts;
             }
             // Reset pressed/released
             b.status &= Status::Held as u8;
         }
-        // Handle events
-        let mut event = unsafe { mem::zeroed() };
-        while unsafe { sdl2::SDL_PollEvent(&mut event) } != 0 {
-            self.update_event(&event);
+        // Update controllers
+        self.controllers_added.clear();
+        self.controllers_removed.clear();
+        for (_, c) in &mut self.controllers {
+            c.begin_update(ts);
+        }
+        // Update touch
+        self.fingers.retain(|f| f.held());
+        for f in &mut self.fingers {
+            f.duration += ts;
+            f.status = Status::Held as u8;
+            f.delta = PointF { x: 0.0, y: 0.0 };
+            f.world = touch_to_world(&self.camera, f.pos);
+        }
+        self.gesture.reset_frame();
+        self.file_drop.reset_frame();
+    }
+
+    // SDL window units to the current coordinate unit
+    fn to_unit(&self, p: Point) -> Point {
+        match self.coord_unit {
+            CoordUnit::Window => p,
+            CoordUnit::Pixels => Point {
+                x: (p.x as f32 * self.pixel_scale.w).round() as i32,
+                y: (p.y as f32 * self.pixel_scale.h).round() as i32,
+            },
+        }
+    }
+
+    fn dim_to_unit(&self, d: Dimensions) -> Dimensions {
+        let p = self.to_unit(Point { x: d.w, y: d.h });
+        Dimensions { w: p.x, h: p.y }
+    }
+
+    fn update_pixel_scale(&mut self, window_id: u32, src: &dyn EventSource) {
+        if let Some((size, drawable)) = src.window_size(window_id) {
+            self.pixel_scale = pixel_scale(size, drawable);
+        }
+    }
+
+    // Window coordinates to camera coordinates
+    pub fn to_world(&self, p: Point) -> Point {
+        if self.screen.w == 0 || self.screen.h == 0 {
+            return p;
+        }
+        Point {
+            x: (p.x as f32 * self.camera.w() / self.screen.w as f32 + self.camera.x()) as i32,
+            y: (p.y as f32 * self.camera.h() / self.screen.h as f32 + self.camera.y()) as i32,
         }
     }
 
-    fn update_event(&mut self, event: &sdl2::SDL_Event) {
+    // src is only queried for state the event doesn't carry, e.g. the window size
+    pub(crate) fn update_event(&mut self, event: &sdl2::SDL_Event, src: &dyn EventSource) {
         match FromPrimitive::from_u32(unsafe { event.type_ }) {
             Some(sdl2::SDL_EventType::SDL_QUIT) => {
                 self.quit = true;
             }
-            Some(sdl2::SDL_EventType::SDL_WINDOWEVENT) => match FromPrimitive::from_u8(unsafe {
-                event.window.event
-            }) {
-                Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_SHOWN) => {
-                    unsafe {
-                        let window = sdl2::SDL_GetWindowFromID(event.window.windowID);
-                        sdl2::SDL_GetWindowSize(window, &mut self.old_dim.w, &mut self.old_dim.h);
+            Some(sdl2::SDL_EventType::SDL_WINDOWEVENT) => {
+                match FromPrimitive::from_u8(unsafe { event.window.event }) {
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_SHOWN) => {
+                        let window_id = unsafe { event.window.windowID };
+                        self.update_pixel_scale(window_id, src);
+                        if let Some((size, _)) = src.window_size(window_id) {
+                            self.old_dim = self.dim_to_unit(size);
+                            self.new_dim = self.old_dim;
+                        }
                     }
-                    self.new_dim = self.old_dim;
-                }
-                Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_RESIZED) => {
-                    self.resized = true;
-                    self.old_dim = self.new_dim;
-                    self.new_dim = Dimensions {
-                        w: unsafe { event.window.data1 },
-                        h: unsafe { event.window.data2 },
-                    };
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_RESIZED) => {
+                        self.update_pixel_scale(unsafe { event.window.windowID }, src);
+                        self.resized = true;
+                        self.old_dim = self.new_dim;
+                        self.new_dim = self.dim_to_unit(Dimensions {
+                            w: unsafe { event.window.data1 },
+                            h: unsafe { event.window.data2 },
+                        });
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_SIZE_CHANGED)
+                    | Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_DISPLAY_CHANGED) => {
+                        self.update_pixel_scale(unsafe { event.window.windowID }, src);
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_FOCUS_GAINED) => {
+                        self.focus_gained = true;
+                        self.has_focus = true;
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_FOCUS_LOST) => {
+                        self.focus_lost = true;
+                        self.has_focus = false;
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_MINIMIZED) => {
+                        self.minimized = true;
+                        self.is_minimized = true;
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_MAXIMIZED) => {
+                        self.maximized = true;
+                        self.is_minimized = false;
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_RESTORED) => {
+                        self.restored = true;
+                        self.is_minimized = false;
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_ENTER) => {
+                        self.mouse_entered = true;
+                        self.has_mouse = true;
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_LEAVE) => {
+                        self.mouse_left = true;
+                        self.has_mouse = false;
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_MOVED) => {
+                        self.moved = true;
+                        self.window_pos = Point {
+                            x: unsafe { event.window.data1 },
+                            y: unsafe { event.window.data2 },
+                        };
+                    }
+                    Some(sdl2::SDL_WindowEventID::SDL_WINDOWEVENT_CLOSE) => {
+                        self.close_requested = true;
+                    }
+                    _ => {}
                 }
-                _ => {}
-            },
+            }
             Some(sdl2::SDL_EventType::SDL_MOUSEBUTTONDOWN) => {
+                self.move_mouse(Point {
+                    x: unsafe { event.button.x },
+                    y: unsafe { event.button.y },
+                });
                 match Mouse::from_u8(unsafe { event.button.button }) {
                     Some(b) => {
                         let button = &mut self.mouse_buttons[b as usize];
                         button.status = Status::Down as u8 | Status::Held as u8;
                         button.duration = 0;
                         button.click_pos = self.mouse;
+                        button.drag_pos = self.mouse;
+                        button.clicks = unsafe { event.button.clicks };
                     }
                     None => {}
                 }
             }
             Some(sdl2::SDL_EventType::SDL_MOUSEBUTTONUP) => {
+                self.move_mouse(Point {
+                    x: unsafe { event.button.x },
+                    y: unsafe { event.button.y },
+                });
                 match Mouse::from_u8(unsafe { event.button.button }) {
                     Some(b) => {
                         let button = &mut self.mouse_buttons[b as usize];
-                        let max_click_diff = MAX_CLICK_DIFF;
-                        button.status = if button.click_pos.dist(&self.mouse) < max_click_diff {
+                        // A drag can start and end in the same frame
+                        button.status = if button.dragging() {
+                            button.drag_pos = self.mouse;
+                            (button.status & Status::DragStart as u8)
+                                | Status::DragEnd as u8
+                                | Status::Up as u8
+                        } else if button.click_pos.dist(&self.mouse) < MAX_CLICK_DIFF {
                             Status::Pressed as u8 | Status::Up as u8
                         } else {
                             Status::Up as u8
@@ -258,42 +660,157 @@ impl Event {
                 }
             }
             Some(sdl2::SDL_EventType::SDL_MOUSEMOTION) => {
-                self.mouse_delta = Point {
+                let delta = self.to_unit(Point {
                     x: unsafe { event.motion.xrel },
                     y: unsafe { event.motion.yrel },
-                };
+                });
+                // Motion can arrive as many small events per frame
+                self.mouse_delta.x += delta.x;
+                self.mouse_delta.y += delta.y;
+                self.move_mouse(Point {
+                    x: unsafe { event.motion.x },
+                    y: unsafe { event.motion.y },
+                });
             }
             Some(sdl2::SDL_EventType::SDL_MOUSEWHEEL) => {
-                self.scroll = -unsafe { event.wheel.y };
+                let wheel = unsafe { event.wheel };
+                let flip = if wheel.direction
+                    == sdl2::SDL_MouseWheelDirection::SDL_MOUSEWHEEL_FLIPPED as u32
+                {
+                    -1
+                } else {
+                    1
+                };
+                // Several wheel events can arrive per frame
+                self.scroll -= wheel.y * flip;
+                self.scroll_x += wheel.x * flip;
+                self.scroll_precise.x += wheel.preciseX * flip as f32;
+                self.scroll_precise.y -= wheel.preciseY * flip as f32;
+                self.scroll_abs_mouse = self.to_unit(Point {
+                    x: wheel.mouseX,
+                    y: wheel.mouseY,
+                });
+                self.scroll_mouse = self.to_world(self.scroll_abs_mouse);
             }
             Some(sdl2::SDL_EventType::SDL_KEYDOWN) => {
-                match FromPrimitive::from_i32(unsafe { event.key.keysym.sym }) {
+                let keysym = unsafe { event.key.keysym };
+                self.mods = keysym.mod_;
+                let key: Option<sdl2::SDL_KeyCode> = FromPrimitive::from_i32(keysym.sym);
+                let repeat = unsafe { event.key.repeat } != 0;
+                self.get_scancode_mut(keysym.scancode, key).press(repeat);
+                match key {
                     Some(k) => {
-                        let b = self.get_key_mut(k);
-                        let held = b.held();
-                        b.status = Status::Pressed as u8 | Status::Held as u8;
-                        if !held {
-                            b.status |= Status::Down as u8;
-                            b.duration = 0;
-                        }
-                        if unsafe { sdl2::SDL_IsTextInputActive() } == sdl2::SDL_bool::SDL_TRUE {
-                            self.process_text_input_key(k);
+                        self.get_key_mut(k, keysym.scancode).press(repeat);
+                        if self.text_input_active {
+                            self.process_text_input_key(k, keysym.mod_, src);
                         }
                     }
                     None => {}
                 }
             }
             Some(sdl2::SDL_EventType::SDL_KEYUP) => {
-                match FromPrimitive::from_i32(unsafe { event.key.keysym.sym }) {
-                    Some(k) => {
-                        let b = self.get_key_mut(k);
-                        b.status = Status::Up as u8;
-                    }
+                let keysym = unsafe { event.key.keysym };
+                self.mods = keysym.mod_;
+                let key: Option<sdl2::SDL_KeyCode> = FromPrimitive::from_i32(keysym.sym);
+                self.get_scancode_mut(keysym.scancode, key).release();
+                match key {
+                    Some(k) => self.get_key_mut(k, keysym.scancode).release(),
                     None => {}
                 }
             }
-            Some(sdl2::SDL_EventType::SDL_TEXTEDITING) => {}
+            Some(sdl2::SDL_EventType::SDL_CONTROLLERDEVICEADDED) => {
+                match src
+                    .open_controller(unsafe { event.cdevice.which }, self.controller_dead_zones)
+                {
+                    Ok(c) => {
+                        self.controllers_added.push(c.instance_id);
+                        self.controllers.insert(c.instance_id, c);
+                    }
+                    Err(e) => println!("Event::update_event() - {}", e),
+                }
+            }
+            Some(sdl2::SDL_EventType::SDL_CONTROLLERDEVICEREMOVED) => {
+                let id = unsafe { event.cdevice.which };
+                if self.controllers.remove(&id).is_some() {
+                    self.controllers_removed.push(id);
+                }
+            }
+            Some(sdl2::SDL_EventType::SDL_CONTROLLERBUTTONDOWN) => {
+                match (
+                    self.controllers.get_mut(unsafe { &event.cbutton.which }),
+                    FromPrimitive::from_u8(unsafe { event.cbutton.button }),
+                ) {
+                    (Some(c), Some(b)) => c.button_down(b),
+                    _ => {}
+                }
+            }
+            Some(sdl2::SDL_EventType::SDL_CONTROLLERBUTTONUP) => {
+                match (
+                    self.controllers.get_mut(unsafe { &event.cbutton.which }),
+                    FromPrimitive::from_u8(unsafe { event.cbutton.button }),
+                ) {
+                    (Some(c), Some(b)) => c.button_up(b),
+                    _ => {}
+                }
+            }
+            Some(sdl2::SDL_EventType::SDL_CONTROLLERAXISMOTION) => {
+                match (
+                    self.controllers.get_mut(unsafe { &event.caxis.which }),
+                    FromPrimitive::from_u8(unsafe { event.caxis.axis }),
+                ) {
+                    (Some(c), Some(a)) => c.set_axis(a, unsafe { event.caxis.value }),
+                    _ => {}
+                }
+            }
+            Some(sdl2::SDL_EventType::SDL_FINGERDOWN) => {
+                let t = unsafe { event.tfinger };
+                let mut f =
+                    Finger::new(t.touchId, t.fingerId, PointF { x: t.x, y: t.y }, t.pressure);
+                f.world = touch_to_world(&self.camera, f.pos);
+                self.fingers.push(f);
+            }
+            Some(sdl2::SDL_EventType::SDL_FINGERMOTION) => {
+                let t = unsafe { event.tfinger };
+                let camera = self.camera;
+                if let Some(f) = self.get_finger_mut(t.touchId, t.fingerId) {
+                    f.pos = PointF { x: t.x, y: t.y };
+                    f.delta.x += t.dx;
+                    f.delta.y += t.dy;
+                    f.pressure = t.pressure;
+                    f.world = touch_to_world(&camera, f.pos);
+                }
+            }
+            Some(sdl2::SDL_EventType::SDL_FINGERUP) => {
+                let t = unsafe { event.tfinger };
+                let camera = self.camera;
+                if let Some(f) = self.get_finger_mut(t.touchId, t.fingerId) {
+                    f.pos = PointF { x: t.x, y: t.y };
+                    f.world = touch_to_world(&camera, f.pos);
+                    f.status = Status::Up as u8;
+                }
+                if self.fingers.iter().filter(|f| f.held()).count() < 2 {
+                    self.gesture.end();
+                }
+            }
+            Some(sdl2::SDL_EventType::SDL_MULTIGESTURE) => {
+                let g = unsafe { event.mgesture };
+                self.gesture
+                    .update(g.numFingers, PointF { x: g.x, y: g.y }, g.dDist, g.dTheta);
+            }
+            Some(sdl2::SDL_EventType::SDL_TEXTEDITING) => {
+                let edit = unsafe { event.edit };
+                self.input_composition =
+                    unsafe { std::ffi::CStr::from_ptr(edit.text.as_ptr() as *const _) }
+                        .to_string_lossy()
+                        .to_string();
+                self.input_composition_cursor = edit.start;
+                self.input_composition_len = edit.length;
+            }
             Some(sdl2::SDL_EventType::SDL_TEXTINPUT) => {
+                // Committing ends the composition
+                self.input_composition.clear();
+                self.input_composition_cursor = 0;
+                self.input_composition_len = 0;
                 let text =
                     unsafe { std::ffi::CStr::from_ptr(event.text.text.as_ptr() as *const _) }
                         .to_string_lossy()
@@ -301,11 +818,91 @@ impl Event {
                         .to_string();
                 self.input_text.push_str(&text);
             }
+            Some(sdl2::SDL_EventType::SDL_DROPBEGIN) => {
+                self.file_drop.began = true;
+                self.file_drop.active = true;
+                self.update_drop_pos(unsafe { event.drop.windowID }, src);
+            }
+            Some(sdl2::SDL_EventType::SDL_DROPFILE) | Some(sdl2::SDL_EventType::SDL_DROPTEXT) => {
+                let drop = unsafe { event.drop };
+                if drop.file.is_null() {
+                    return;
+                }
+                // The string is freed by the event source
+                let s = unsafe { std::ffi::CStr::from_ptr(drop.file) }
+                    .to_string_lossy()
+                    .to_string();
+                if drop.type_ == sdl2::SDL_EventType::SDL_DROPFILE as u32 {
+                    self.file_drop.files.push(s);
+                } else {
+                    self.file_drop.texts.push(s);
+                }
+                self.update_drop_pos(drop.windowID, src);
+            }
+            Some(sdl2::SDL_EventType::SDL_DROPCOMPLETE) => {
+                self.file_drop.completed = true;
+                self.file_drop.active = false;
+            }
             _ => {}
         }
     }
 
-    fn process_text_input_key(&mut self, key: sdl2::SDL_KeyCode) {
+    // Position of a mouse event in window pixels, buttons start dragging as soon as the
+    // mouse gets far enough from where they went down
+    fn move_mouse(&mut self, p: Point) {
+        self.abs_mouse = self.to_unit(p);
+        self.mouse = self.to_world(self.abs_mouse);
+        self.update_drags();
+    }
+
+    fn update_drags(&mut self) {
+        for b in &mut self.mouse_buttons {
+            if !b.held() {
+                continue;
+            }
+            if b.dragging() {
+                b.drag_pos = self.mouse;
+            } else if b.click_pos.dist(&self.mouse) >= MAX_CLICK_DIFF {
+                b.status |= Status::DragStart as u8 | Status::Dragging as u8;
+                b.drag_pos = self.mouse;
+            }
+        }
+    }
+
+    // The mouse usually isn't tracked while the OS drags something over the window,
+    // so the drop position comes from the global mouse position
+    fn update_drop_pos(&mut self, window_id: u32, src: &dyn EventSource) {
+        self.file_drop.abs_pos = match src.global_mouse_state(window_id) {
+            Some(p) => self.to_unit(p),
+            None => self.abs_mouse,
+        };
+        self.file_drop.pos = self.to_world(self.file_drop.abs_pos);
+    }
+
+    fn process_text_input_key(&mut self, key: sdl2::SDL_KeyCode, mods: u16, src: &dyn EventSource) {
+        // Keys are left to the IME while it is composing
+        if !self.input_composition.is_empty() {
+            return;
+        }
+        if mods & SHORTCUT_MODS != 0 {
+            match key {
+                sdl2::SDL_KeyCode::SDLK_c => self.input_copy = true,
+                sdl2::SDL_KeyCode::SDLK_x => self.input_cut = true,
+                sdl2::SDL_KeyCode::SDLK_a => self.input_select_all = true,
+                sdl2::SDL_KeyCode::SDLK_v => {
+                    self.input_paste = true;
+                    if let Some(text) = src.clipboard_text() {
+                        self.input_text.push_str(&text);
+                    }
+                }
+                sdl2::SDL_KeyCode::SDLK_z if mods & SELECT_MODS != 0 => self.input_redo = true,
+                sdl2::SDL_KeyCode::SDLK_z => self.input_undo = true,
+                sdl2::SDL_KeyCode::SDLK_y => self.input_redo = true,
+                _ => {}
+            }
+        }
+        let select = mods & SELECT_MODS != 0;
+        let word = mods & WORD_MODS != 0;
         match key {
             sdl2::SDL_KeyCode::SDLK_BACKSPACE => {
                 if self.input_text.is_empty() {
@@ -318,16 +915,28 @@ impl Event {
                 self.input_delete += 1;
             }
             sdl2::SDL_KeyCode::SDLK_LEFT => {
-                self.input_move -= 1;
+                if word {
+                    self.input_word_move -= 1;
+                } else {
+                    self.input_move -= 1;
+                }
+                self.input_select = select;
             }
             sdl2::SDL_KeyCode::SDLK_RIGHT => {
-                self.input_move += 1;
+                if word {
+                    self.input_word_move += 1;
+                } else {
+                    self.input_move += 1;
+                }
+                self.input_select = select;
             }
             sdl2::SDL_KeyCode::SDLK_HOME => {
                 self.input_seek = InputSeek::Start;
+                self.input_select = select;
             }
             sdl2::SDL_KeyCode::SDLK_END => {
                 self.input_seek = InputSeek::End;
+                self.input_select = select;
             }
             _ => {}
         }
@@ -337,10 +946,53 @@ impl Event {
         self.mouse_delta.x != 0 || self.mouse_delta.y != 0
     }
 
-    fn get_key_mut(&mut self, key: sdl2::SDL_KeyCode) -> &mut KeyButton {
-        self.key_buttons
+    fn get_key_mut(
+        &mut self,
+        key: sdl2::SDL_KeyCode,
+        scancode: sdl2::SDL_Scancode,
+    ) -> &mut KeyButton {
+        let b = self
+            .key_buttons
             .entry(key)
-            .or_insert_with(|| KeyButton::new(key))
+            .or_insert_with(|| KeyButton::new(key, scancode));
+        b.scancode = scancode;
+        b
+    }
+
+    fn get_scancode_mut(
+        &mut self,
+        scancode: sdl2::SDL_Scancode,
+        key: Option<sdl2::SDL_KeyCode>,
+    ) -> &mut KeyButton {
+        let key = key.unwrap_or(sdl2::SDL_KeyCode::SDLK_UNKNOWN);
+        let b = self
+            .scan_buttons
+            .entry(scancode)
+            .or_insert_with(|| KeyButton::new(key, scancode));
+        b.key = key;
+        b
+    }
+
+    pub fn get_scancode(&self, scancode: sdl2::SDL_Scancode) -> Option<&KeyButton> {
+        self.scan_buttons.get(&scancode)
+    }
+
+    pub fn mod_held(&self, m: Mod) -> bool {
+        m.held(self.mods)
+    }
+
+    // The chord's key went down this frame with exactly its modifiers held
+    pub fn chord_pressed(&self, chord: &Chord) -> bool {
+        chord.mods_match(self.mods) && self.get_key(chord.key).map_or(false, |b| b.down())
+    }
+
+    // Like chord_pressed() but also true on key repeats
+    pub fn chord_repeated(&self, chord: &Chord) -> bool {
+        chord.mods_match(self.mods) && self.get_key(chord.key).map_or(false, |b| b.pressed())
+    }
+
+    pub fn chord_held(&self, chord: &Chord) -> bool {
+        chord.mods_match(self.mods) && self.get_key(chord.key).map_or(false, |b| b.held())
     }
 
     pub fn get_key(&self, key: sdl2::SDL_KeyCode) -> Option<&KeyButton> {
@@ -350,6 +1002,56 @@ impl Event {
         }
     }
 
+    pub fn get_controller(&self, instance_id: i32) -> Option<&Controller> {
+        self.controllers.get(&instance_id)
+    }
+
+    // Any connected controller, for single player games
+    pub fn first_controller(&self) -> Option<&Controller> {
+        self.controllers
+            .keys()
+            .min()
+            .and_then(|id| self.controllers.get(id))
+    }
+
+    // SDL may reuse the id of a finger lifted this frame, only the held one moves
+    fn get_finger_mut(&mut self, touch_id: i64, id: i64) -> Option<&mut Finger> {
+        self.fingers
+            .iter_mut()
+            .find(|f| f.touch_id == touch_id && f.id == id && f.held())
+    }
+
+    // The newest finger with the id, a reused id is held while the lifted one is up
+    pub fn get_finger(&self, touch_id: i64, id: i64) -> Option<&Finger> {
+        self.fingers
+            .iter()
+            .rev()
+            .find(|f| f.touch_id == touch_id && f.id == id)
+    }
+
+    pub fn fingers_held(&self) -> usize {
+        self.fingers.iter().filter(|f| f.held()).count()
+    }
+
+    // Normalized touch position to camera coordinates
+    pub fn touch_to_world(&self, p: PointF) -> PointF {
+        touch_to_world(&self.camera, p)
+    }
+
+    // Pinch zoom factor this frame, 1 without a pinch
+    pub fn pinch_zoom(&self) -> f32 {
+        self.gesture.zoom(&self.fingers)
+    }
+
+    // Two-finger pan this frame in camera units
+    // Move the camera by the negative to keep the world under the fingers
+    pub fn pan_world(&self) -> PointF {
+        PointF {
+            x: self.gesture.pan.x * self.camera.w(),
+            y: self.gesture.pan.y * self.camera.h(),
+        }
+    }
+
     pub fn get_sdl_mouse(&self, sdl_button_type: u8) -> &MouseButton {
         &self.mouse_buttons[sdl_button_type as usize]
     }
@@ -358,3 +1060,35 @@ impl Event {
         &self.mouse_buttons[button as usize]
     }
 }
+
+// Window an event belongs to, None for events that aren't tied to a window
+pub fn event_window_id(event: &sdl2::SDL_Event) -> Option<u32> {
+    let id = unsafe {
+        match FromPrimitive::from_u32(event.type_) {
+            Some(sdl2::SDL_EventType::SDL_WINDOWEVENT) => event.window.windowID,
+            Some(sdl2::SDL_EventType::SDL_KEYDOWN) | Some(sdl2::SDL_EventType::SDL_KEYUP) => {
+                event.key.windowID
+            }
+            Some(sdl2::SDL_EventType::SDL_TEXTEDITING) => event.edit.windowID,
+            Some(sdl2::SDL_EventType::SDL_TEXTINPUT) => event.text.windowID,
+            Some(sdl2::SDL_EventType::SDL_MOUSEMOTION) => event.motion.windowID,
+            Some(sdl2::SDL_EventType::SDL_MOUSEBUTTONDOWN)
+            | Some(sdl2::SDL_EventType::SDL_MOUSEBUTTONUP) => event.button.windowID,
+            Some(sdl2::SDL_EventType::SDL_MOUSEWHEEL) => event.wheel.windowID,
+            Some(sdl2::SDL_EventType::SDL_FINGERDOWN)
+            | Some(sdl2::SDL_EventType::SDL_FINGERUP)
+            | Some(sdl2::SDL_EventType::SDL_FINGERMOTION) => event.tfinger.windowID,
+            Some(sdl2::SDL_EventType::SDL_DROPFILE)
+            | Some(sdl2::SDL_EventType::SDL_DROPTEXT)
+            | Some(sdl2::SDL_EventType::SDL_DROPBEGIN)
+            | Some(sdl2::SDL_EventType::SDL_DROPCOMPLETE) => event.drop.windowID,
+            _ => 0,
+        }
+    };
+    // Id 0 means no window had focus
+    if id == 0 {
+        None
+    } else {
+        Some(id)
+    }
+}
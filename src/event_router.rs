@@ -0,0 +1,128 @@
+use num_traits::FromPrimitive;
+
+use crate::event::{event_window_id, Event};
+use crate::event_source::{EventSource, SdlEventSource};
+use crate::pointers::Window;
+use crate::rect::{Dimensions, Rect};
+use crate::sdl2;
+
+use std::collections::HashMap;
+use std::mem;
+
+// Polls SDL once per frame and hands each event to the Event of the window it belongs to
+// Events that aren't tied to a window, such as SDL_QUIT, go to every window
+// Controllers are opened once and tracked by the Event of the controller window only
+pub struct EventRouter {
+    events: HashMap<u32, Event>,
+    controller_window: Option<u32>,
+    pub quit: bool,
+}
+
+impl EventRouter {
+    pub fn new() -> Self {
+        EventRouter {
+            events: HashMap::new(),
+            controller_window: None,
+            quit: false,
+        }
+    }
+
+    // The first window added also receives controller input
+    pub fn add_window(&mut self, win: &Window) -> u32 {
+        let id = win.id();
+        self.events.insert(id, Event::for_window(id));
+        if self.controller_window.is_none() {
+            self.controller_window = Some(id);
+        }
+        id
+    }
+
+    // Controllers move to another window if this one had them
+    pub fn remove_window(&mut self, window_id: u32) {
+        let e = self.events.remove(&window_id);
+        if self.controller_window == Some(window_id) {
+            self.controller_window = None;
+            if let Some(&id) = self.events.keys().min() {
+                if let (Some(old), Some(new)) = (e, self.events.get_mut(&id)) {
+                    new.controllers = old.controllers;
+                }
+                self.controller_window = Some(id);
+            }
+        }
+    }
+
+    pub fn controller_window(&self) -> Option<u32> {
+        self.controller_window
+    }
+
+    // Move the open controllers and their state to another window's Event
+    pub fn set_controller_window(&mut self, window_id: u32) {
+        if !self.events.contains_key(&window_id) {
+            return;
+        }
+        let controllers = self
+            .controller_window
+            .and_then(|id| self.events.get_mut(&id))
+            .map(|e| mem::take(&mut e.controllers));
+        if let (Some(c), Some(e)) = (controllers, self.events.get_mut(&window_id)) {
+            e.controllers = c;
+        }
+        self.controller_window = Some(window_id);
+    }
+
+    pub fn get(&self, window_id: u32) -> Option<&Event> {
+        self.events.get(&window_id)
+    }
+
+    pub fn get_mut(&mut self, window_id: u32) -> Option<&mut Event> {
+        self.events.get_mut(&window_id)
+    }
+
+    pub fn set_view(&mut self, window_id: u32, camera: &Rect, screen: &Dimensions) {
+        if let Some(e) = self.events.get_mut(&window_id) {
+            e.set_view(camera, screen);
+        }
+    }
+
+    pub fn update(&mut self, ts: u32) {
+        let mut src = SdlEventSource::new();
+        for e in self.events.values_mut() {
+            e.begin_update(ts, &src);
+        }
+        while let Some(event) = src.poll() {
+            if is_controller_event(&event) {
+                if let Some(e) = self
+                    .controller_window
+                    .and_then(|id| self.events.get_mut(&id))
+                {
+                    e.update_event(&event, &src);
+                }
+                continue;
+            }
+            match event_window_id(&event) {
+                Some(id) => {
+                    if let Some(e) = self.events.get_mut(&id) {
+                        e.update_event(&event, &src);
+                    }
+                }
+                None => {
+                    for e in self.events.values_mut() {
+                        e.update_event(&event, &src);
+                    }
+                }
+            }
+        }
+        self.quit = self.events.values().any(|e| e.quit);
+    }
+}
+
+fn is_controller_event(event: &sdl2::SDL_Event) -> bool {
+    matches!(
+        FromPrimitive::from_u32(unsafe { event.type_ }),
+        Some(sdl2::SDL_EventType::SDL_CONTROLLERDEVICEADDED)
+            | Some(sdl2::SDL_EventType::SDL_CONTROLLERDEVICEREMOVED)
+            | Some(sdl2::SDL_EventType::SDL_CONTROLLERBUTTONDOWN)
+            | Some(sdl2::SDL_EventType::SDL_CONTROLLERBUTTONUP)
+            | Some(sdl2::SDL_EventType::SDL_CONTROLLERAXISMOTION)
+    )
+}
@@ -0,0 +1,784 @@
+use crate::clipboard;
+use crate::controller::{Controller, DeadZones};
+use crate::error::Result;
+use crate::event::Mouse;
+use crate::rect::{Dimensions, Point};
+use crate::sdl2;
+
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::c_char;
+
+// Where Event gets raw input from, see Event::update_from()
+pub trait EventSource {
+    // Called once at the start of every frame, before any other method
+    fn begin_frame(&mut self) {}
+
+    // Next event of this frame, None once the frame has no more events
+    fn poll(&mut self) -> Option<sdl2::SDL_Event>;
+
+    // Mouse position in window units, None if the mouse isn't over the window
+    // A window_id of 0 means any window
+    fn mouse_state(&self, window_id: u32) -> Option<Point>;
+
+    fn relative_mouse_mode(&self) -> bool;
+
+    fn text_input_active(&self) -> bool;
+
+    // Window size in window units and in pixels, None if there is no such window
+    fn window_size(&self, window_id: u32) -> Option<(Dimensions, Dimensions)>;
+
+    // Mouse position relative to a window, also while the mouse is outside it, e.g.
+    // during a drag from another application
+    // None if there is no such window
+    fn global_mouse_state(&self, window_id: u32) -> Option<Point>;
+
+    // Text to paste, None if the clipboard is empty
+    fn clipboard_text(&self) -> Option<String>;
+
+    // The controller at a device index, as given by SDL_CONTROLLERDEVICEADDED
+    fn open_controller(&self, device_index: i32, dead_zones: DeadZones) -> Result<Controller>;
+}
+
+// Reads input from SDL, used by Event::update()
+pub struct SdlEventSource {
+    // String of the last drop event, SDL leaves freeing it to us
+    drop_file: *mut c_char,
+}
+
+impl SdlEventSource {
+    pub fn new() -> Self {
+        SdlEventSource {
+            drop_file: std::ptr::null_mut(),
+        }
+    }
+
+    fn free_drop_file(&mut self) {
+        if !self.drop_file.is_null() {
+            unsafe { sdl2::SDL_free(self.drop_file as *mut _) };
+            self.drop_file = std::ptr::null_mut();
+        }
+    }
+}
+
+impl Drop for SdlEventSource {
+    fn drop(&mut self) {
+        self.free_drop_file();
+    }
+}
+
+impl EventSource for SdlEventSource {
+    // A dropped string stays valid until the next poll, which may hand the event to
+    // several Events first
+    fn poll(&mut self) -> Option<sdl2::SDL_Event> {
+        self.free_drop_file();
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        if unsafe { sdl2::SDL_PollEvent(&mut event) } != 0 {
+            let type_ = unsafe { event.type_ };
+            if type_ == sdl2::SDL_EventType::SDL_DROPFILE as u32
+                || type_ == sdl2::SDL_EventType::SDL_DROPTEXT as u32
+            {
+                self.drop_file = unsafe { event.drop.file };
+            }
+            Some(event)
+        } else {
+            None
+        }
+    }
+
+    fn mouse_state(&self, window_id: u32) -> Option<Point> {
+        if window_id != 0 {
+            let focus = unsafe { sdl2::SDL_GetMouseFocus() };
+            if focus.is_null() || unsafe { sdl2::SDL_GetWindowID(focus) } != window_id {
+                return None;
+            }
+        }
+        let (mut x, mut y) = (0, 0);
+        unsafe {
+            sdl2::SDL_GetMouseState(&mut x, &mut y);
+        }
+        Some(Point { x, y })
+    }
+
+    fn relative_mouse_mode(&self) -> bool {
+        unsafe { sdl2::SDL_GetRelativeMouseMode() == sdl2::SDL_bool::SDL_TRUE }
+    }
+
+    fn text_input_active(&self) -> bool {
+        unsafe { sdl2::SDL_IsTextInputActive() == sdl2::SDL_bool::SDL_TRUE }
+    }
+
+    fn window_size(&self, window_id: u32) -> Option<(Dimensions, Dimensions)> {
+        let window = unsafe { sdl2::SDL_GetWindowFromID(window_id) };
+        if window.is_null() {
+            return None;
+        }
+        let (mut size, mut drawable) = (Dimensions { w: 0, h: 0 }, Dimensions { w: 0, h: 0 });
+        unsafe {
+            sdl2::SDL_GetWindowSize(window, &mut size.w, &mut size.h);
+            sdl2::SDL_GetWindowSizeInPixels(window, &mut drawable.w, &mut drawable.h);
+        }
+        Some((size, drawable))
+    }
+
+    fn global_mouse_state(&self, window_id: u32) -> Option<Point> {
+        let window = unsafe { sdl2::SDL_GetWindowFromID(window_id) };
+        if window.is_null() {
+            return None;
+        }
+        let (mut x, mut y, mut win_x, mut win_y) = (0, 0, 0, 0);
+        unsafe {
+            sdl2::SDL_GetGlobalMouseState(&mut x, &mut y);
+            sdl2::SDL_GetWindowPosition(window, &mut win_x, &mut win_y);
+        }
+        Some(Point {
+            x: x - win_x,
+            y: y - win_y,
+        })
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        clipboard::get_clipboard_text()
+    }
+
+    fn open_controller(&self, device_index: i32, dead_zones: DeadZones) -> Result<Controller> {
+        Controller::open(device_index, dead_zones)
+    }
+}
+
+// ScriptedEventSource
+// Synthetic input without a window or an SDL event queue, e.g.
+//     src.mouse_down(Mouse::Left, 10, 10).end_frame()
+//         .mouse_move(20, 0).end_frame()
+//         .mouse_up(Mouse::Left).end_frame();
+// then one Event::update_from() per scripted frame
+// The mouse position reported for a frame is where its last event left the mouse
+struct ScriptedFrame {
+    events: VecDeque<sdl2::SDL_Event>,
+    mouse: Point,
+    relative_mouse: bool,
+    text_input: bool,
+    clipboard: Option<String>,
+}
+
+pub struct ScriptedEventSource {
+    frames: VecDeque<ScriptedFrame>,
+    current: ScriptedFrame,
+    // State while scripting
+    events: VecDeque<sdl2::SDL_Event>,
+    mouse: Point,
+    relative_mouse: bool,
+    text_input: bool,
+    clipboard: Option<String>,
+    // Keeps the strings of scripted drop events alive
+    drop_strings: Vec<CString>,
+    // Size and pixel size, there is no window unless set
+    window_size: Option<(Dimensions, Dimensions)>,
+    pub window_id: u32,
+}
+
+impl ScriptedEventSource {
+    pub fn new() -> Self {
+        ScriptedEventSource {
+            frames: VecDeque::new(),
+            current: ScriptedFrame {
+                events: VecDeque::new(),
+                mouse: Point { x: 0, y: 0 },
+                relative_mouse: false,
+                text_input: false,
+                clipboard: None,
+            },
+            events: VecDeque::new(),
+            mouse: Point { x: 0, y: 0 },
+            relative_mouse: false,
+            text_input: false,
+            clipboard: None,
+            drop_strings: Vec::new(),
+            window_size: None,
+            window_id: 0,
+        }
+    }
+
+    // Finish the events of the current frame, frames without events are allowed
+    pub fn end_frame(&mut self) -> &mut Self {
+        self.frames.push_back(ScriptedFrame {
+            events: mem::take(&mut self.events),
+            mouse: self.mouse,
+            relative_mouse: self.relative_mouse,
+            text_input: self.text_input,
+            clipboard: self.clipboard.clone(),
+        });
+        self
+    }
+
+    // Frames that haven't been played yet
+    pub fn frames_left(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn push(&mut self, event: sdl2::SDL_Event) -> &mut Self {
+        self.events.push_back(event);
+        self
+    }
+
+    // Move the mouse to a position without a motion event
+    pub fn set_mouse(&mut self, x: i32, y: i32) -> &mut Self {
+        self.mouse = Point { x, y };
+        self
+    }
+
+    pub fn set_relative_mouse_mode(&mut self, enabled: bool) -> &mut Self {
+        self.relative_mouse = enabled;
+        self
+    }
+
+    pub fn set_text_input(&mut self, enabled: bool) -> &mut Self {
+        self.text_input = enabled;
+        self
+    }
+
+    // Pasted by Ctrl+V while text input is active, never touches the system clipboard
+    pub fn set_clipboard(&mut self, text: &str) -> &mut Self {
+        self.clipboard = if text.is_empty() {
+            None
+        } else {
+            Some(text.to_string())
+        };
+        self
+    }
+
+    // drawable differs from size on high DPI displays
+    pub fn set_window_size(&mut self, size: Dimensions, drawable: Dimensions) -> &mut Self {
+        self.window_size = Some((size, drawable));
+        self
+    }
+
+    // Motion relative to the current position
+    pub fn mouse_move(&mut self, dx: i32, dy: i32) -> &mut Self {
+        if !self.relative_mouse {
+            self.mouse.x += dx;
+            self.mouse.y += dy;
+        }
+        let mut motion: sdl2::SDL_MouseMotionEvent = unsafe { mem::zeroed() };
+        motion.type_ = sdl2::SDL_EventType::SDL_MOUSEMOTION as u32;
+        motion.windowID = self.window_id;
+        motion.x = self.mouse.x;
+        motion.y = self.mouse.y;
+        motion.xrel = dx;
+        motion.yrel = dy;
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.motion = motion;
+        self.push(event)
+    }
+
+    pub fn mouse_move_to(&mut self, x: i32, y: i32) -> &mut Self {
+        let (dx, dy) = (x - self.mouse.x, y - self.mouse.y);
+        self.mouse_move(dx, dy)
+    }
+
+    // Press a button at a position, moving the mouse there first
+    pub fn mouse_down(&mut self, button: Mouse, x: i32, y: i32) -> &mut Self {
+        self.mouse_down_clicks(button, x, y, 1)
+    }
+
+    // clicks is SDL's click count, 2 for the second press of a double click
+    pub fn mouse_down_clicks(&mut self, button: Mouse, x: i32, y: i32, clicks: u8) -> &mut Self {
+        if self.mouse.x != x || self.mouse.y != y {
+            self.mouse_move_to(x, y);
+        }
+        self.mouse_button(button, sdl2::SDL_EventType::SDL_MOUSEBUTTONDOWN, clicks)
+    }
+
+    // Release a button at the current position
+    pub fn mouse_up(&mut self, button: Mouse) -> &mut Self {
+        self.mouse_button(button, sdl2::SDL_EventType::SDL_MOUSEBUTTONUP, 1)
+    }
+
+    fn mouse_button(&mut self, button: Mouse, type_: sdl2::SDL_EventType, clicks: u8) -> &mut Self {
+        let mut b: sdl2::SDL_MouseButtonEvent = unsafe { mem::zeroed() };
+        b.type_ = type_ as u32;
+        b.windowID = self.window_id;
+        b.button = match button {
+            Mouse::Left => sdl2::SDL_BUTTON_LEFT,
+            Mouse::Right => sdl2::SDL_BUTTON_RIGHT,
+            Mouse::Middle => sdl2::SDL_BUTTON_MIDDLE,
+            Mouse::X1 => sdl2::SDL_BUTTON_X1,
+            Mouse::X2 => sdl2::SDL_BUTTON_X2,
+        } as u8;
+        b.state = match type_ {
+            sdl2::SDL_EventType::SDL_MOUSEBUTTONDOWN => sdl2::SDL_PRESSED,
+            _ => sdl2::SDL_RELEASED,
+        } as u8;
+        b.clicks = clicks;
+        b.x = self.mouse.x;
+        b.y = self.mouse.y;
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.button = b;
+        self.push(event)
+    }
+
+    // Positive y scrolls away from the user, as in SDL
+    pub fn wheel(&mut self, x: i32, y: i32) -> &mut Self {
+        let mut wheel: sdl2::SDL_MouseWheelEvent = unsafe { mem::zeroed() };
+        wheel.type_ = sdl2::SDL_EventType::SDL_MOUSEWHEEL as u32;
+        wheel.windowID = self.window_id;
+        wheel.x = x;
+        wheel.y = y;
+        wheel.preciseX = x as f32;
+        wheel.preciseY = y as f32;
+        wheel.mouseX = self.mouse.x;
+        wheel.mouseY = self.mouse.y;
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.wheel = wheel;
+        self.push(event)
+    }
+
+    // Scancodes come from the current keyboard layout, which SDL only knows once video
+    // is initialized, use key_down_scancode() to test other layouts
+    pub fn key_down(&mut self, key: sdl2::SDL_KeyCode) -> &mut Self {
+        self.key(
+            key,
+            layout_scancode(key),
+            0,
+            sdl2::SDL_EventType::SDL_KEYDOWN,
+            false,
+        )
+    }
+
+    // mods are SDL_Keymod bits, e.g. KMOD_LCTRL
+    pub fn key_down_with(&mut self, key: sdl2::SDL_KeyCode, mods: u16) -> &mut Self {
+        self.key(
+            key,
+            layout_scancode(key),
+            mods,
+            sdl2::SDL_EventType::SDL_KEYDOWN,
+            false,
+        )
+    }
+
+    pub fn key_down_scancode(
+        &mut self,
+        key: sdl2::SDL_KeyCode,
+        scancode: sdl2::SDL_Scancode,
+        mods: u16,
+    ) -> &mut Self {
+        self.key(key, scancode, mods, sdl2::SDL_EventType::SDL_KEYDOWN, false)
+    }
+
+    // Auto repeat of a key that is already held
+    pub fn key_repeat(&mut self, key: sdl2::SDL_KeyCode) -> &mut Self {
+        self.key(
+            key,
+            layout_scancode(key),
+            0,
+            sdl2::SDL_EventType::SDL_KEYDOWN,
+            true,
+        )
+    }
+
+    pub fn key_up(&mut self, key: sdl2::SDL_KeyCode) -> &mut Self {
+        self.key(
+            key,
+            layout_scancode(key),
+            0,
+            sdl2::SDL_EventType::SDL_KEYUP,
+            false,
+        )
+    }
+
+    pub fn key_up_scancode(
+        &mut self,
+        key: sdl2::SDL_KeyCode,
+        scancode: sdl2::SDL_Scancode,
+        mods: u16,
+    ) -> &mut Self {
+        self.key(key, scancode, mods, sdl2::SDL_EventType::SDL_KEYUP, false)
+    }
+
+    fn key(
+        &mut self,
+        key: sdl2::SDL_KeyCode,
+        scancode: sdl2::SDL_Scancode,
+        mods: u16,
+        type_: sdl2::SDL_EventType,
+        repeat: bool,
+    ) -> &mut Self {
+        let mut k: sdl2::SDL_KeyboardEvent = unsafe { mem::zeroed() };
+        k.type_ = type_ as u32;
+        k.windowID = self.window_id;
+        k.state = match type_ {
+            sdl2::SDL_EventType::SDL_KEYDOWN => sdl2::SDL_PRESSED,
+            _ => sdl2::SDL_RELEASED,
+        } as u8;
+        k.repeat = repeat as u8;
+        k.keysym.sym = key as i32;
+        k.keysym.scancode = scancode;
+        k.keysym.mod_ = mods;
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.key = k;
+        self.push(event)
+    }
+
+    // Text longer than an SDL text event is split over several events
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        let mut chunk = String::new();
+        for c in text.chars() {
+            // Leave room for the nul terminator
+            if chunk.len() + c.len_utf8() >= sdl2::SDL_TEXTINPUTEVENT_TEXT_SIZE as usize {
+                self.text_event(&chunk);
+                chunk.clear();
+            }
+            chunk.push(c);
+        }
+        if !chunk.is_empty() {
+            self.text_event(&chunk);
+        }
+        self
+    }
+
+    fn text_event(&mut self, text: &str) {
+        let mut t: sdl2::SDL_TextInputEvent = unsafe { mem::zeroed() };
+        t.type_ = sdl2::SDL_EventType::SDL_TEXTINPUT as u32;
+        t.windowID = self.window_id;
+        for (dst, src) in t.text.iter_mut().zip(text.bytes()) {
+            *dst = src as _;
+        }
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.text = t;
+        self.push(event);
+    }
+
+    // A complete drop of files, e.g. paths dragged from a file manager
+    pub fn drop_files(&mut self, paths: &[&str]) -> &mut Self {
+        self.drop_event(sdl2::SDL_EventType::SDL_DROPBEGIN, None);
+        for p in paths {
+            self.drop_event(sdl2::SDL_EventType::SDL_DROPFILE, Some(p));
+        }
+        self.drop_event(sdl2::SDL_EventType::SDL_DROPCOMPLETE, None)
+    }
+
+    pub fn drop_text(&mut self, text: &str) -> &mut Self {
+        self.drop_event(sdl2::SDL_EventType::SDL_DROPBEGIN, None);
+        self.drop_event(sdl2::SDL_EventType::SDL_DROPTEXT, Some(text));
+        self.drop_event(sdl2::SDL_EventType::SDL_DROPCOMPLETE, None)
+    }
+
+    fn drop_event(&mut self, type_: sdl2::SDL_EventType, s: Option<&str>) -> &mut Self {
+        let mut d: sdl2::SDL_DropEvent = unsafe { mem::zeroed() };
+        d.type_ = type_ as u32;
+        d.windowID = self.window_id;
+        if let Some(s) = s {
+            // Interior nuls can't be passed through SDL either
+            let cs = CString::new(s.replace('\0', "")).unwrap_or_default();
+            d.file = cs.as_ptr() as *mut _;
+            self.drop_strings.push(cs);
+        }
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.drop = d;
+        self.push(event)
+    }
+
+    // Connect a controller, its id is used as both device index and instance id
+    pub fn controller_add(&mut self, id: i32) -> &mut Self {
+        self.controller_device(sdl2::SDL_EventType::SDL_CONTROLLERDEVICEADDED, id)
+    }
+
+    pub fn controller_remove(&mut self, id: i32) -> &mut Self {
+        self.controller_device(sdl2::SDL_EventType::SDL_CONTROLLERDEVICEREMOVED, id)
+    }
+
+    fn controller_device(&mut self, type_: sdl2::SDL_EventType, id: i32) -> &mut Self {
+        let mut d: sdl2::SDL_ControllerDeviceEvent = unsafe { mem::zeroed() };
+        d.type_ = type_ as u32;
+        d.which = id;
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.cdevice = d;
+        self.push(event)
+    }
+
+    pub fn controller_button_down(
+        &mut self,
+        id: i32,
+        button: sdl2::SDL_GameControllerButton,
+    ) -> &mut Self {
+        self.controller_button(id, button, sdl2::SDL_EventType::SDL_CONTROLLERBUTTONDOWN)
+    }
+
+    pub fn controller_button_up(
+        &mut self,
+        id: i32,
+        button: sdl2::SDL_GameControllerButton,
+    ) -> &mut Self {
+        self.controller_button(id, button, sdl2::SDL_EventType::SDL_CONTROLLERBUTTONUP)
+    }
+
+    fn controller_button(
+        &mut self,
+        id: i32,
+        button: sdl2::SDL_GameControllerButton,
+        type_: sdl2::SDL_EventType,
+    ) -> &mut Self {
+        let mut b: sdl2::SDL_ControllerButtonEvent = unsafe { mem::zeroed() };
+        b.type_ = type_ as u32;
+        b.which = id;
+        b.button = button as u8;
+        b.state = match type_ {
+            sdl2::SDL_EventType::SDL_CONTROLLERBUTTONDOWN => sdl2::SDL_PRESSED,
+            _ => sdl2::SDL_RELEASED,
+        } as u8;
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.cbutton = b;
+        self.push(event)
+    }
+
+    // value is the raw axis value, -32768 - 32767, triggers only go up to 32767
+    pub fn controller_axis(
+        &mut self,
+        id: i32,
+        axis: sdl2::SDL_GameControllerAxis,
+        value: i16,
+    ) -> &mut Self {
+        let mut a: sdl2::SDL_ControllerAxisEvent = unsafe { mem::zeroed() };
+        a.type_ = sdl2::SDL_EventType::SDL_CONTROLLERAXISMOTION as u32;
+        a.which = id;
+        a.axis = axis as u8;
+        a.value = value;
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.caxis = a;
+        self.push(event)
+    }
+
+    pub fn quit(&mut self) -> &mut Self {
+        let mut event: sdl2::SDL_Event = unsafe { mem::zeroed() };
+        event.type_ = sdl2::SDL_EventType::SDL_QUIT as u32;
+        self.push(event)
+    }
+}
+
+fn layout_scancode(key: sdl2::SDL_KeyCode) -> sdl2::SDL_Scancode {
+    unsafe { sdl2::SDL_GetScancodeFromKey(key as i32) }
+}
+
+impl EventSource for ScriptedEventSource {
+    // Once the script runs out, frames have no events and the last state is kept
+    fn begin_frame(&mut self) {
+        if let Some(frame) = self.frames.pop_front() {
+            self.current = frame;
+        } else {
+            self.current.events.clear();
+        }
+    }
+
+    fn poll(&mut self) -> Option<sdl2::SDL_Event> {
+        self.current.events.pop_front()
+    }
+
+    fn mouse_state(&self, _window_id: u32) -> Option<Point> {
+        Some(self.current.mouse)
+    }
+
+    fn relative_mouse_mode(&self) -> bool {
+        self.current.relative_mouse
+    }
+
+    fn text_input_active(&self) -> bool {
+        self.current.text_input
+    }
+
+    fn window_size(&self, _window_id: u32) -> Option<(Dimensions, Dimensions)> {
+        self.window_size
+    }
+
+    // The scripted mouse is tracked outside the window too
+    fn global_mouse_state(&self, _window_id: u32) -> Option<Point> {
+        Some(self.current.mouse)
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        self.current.clipboard.clone()
+    }
+
+    // Scripted controllers have no device, the device index is the instance id
+    fn open_controller(&self, device_index: i32, dead_zones: DeadZones) -> Result<Controller> {
+        Ok(Controller::detached(
+            device_index,
+            "Scripted Controller",
+            dead_zones,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Event;
+    use crate::rect::Rect;
+
+    const SPACE: sdl2::SDL_KeyCode = sdl2::SDL_KeyCode::SDLK_SPACE;
+
+    // Camera and screen are left empty, so world coordinates are window coordinates
+    fn frame(e: &mut Event, src: &mut ScriptedEventSource) {
+        e.update_from(src, 16, &Rect::new(), &Dimensions { w: 0, h: 0 });
+    }
+
+    #[test]
+    fn click() {
+        let mut src = ScriptedEventSource::new();
+        src.mouse_down(Mouse::Left, 10, 10)
+            .end_frame()
+            .mouse_move(3, 0)
+            .mouse_up(Mouse::Left)
+            .end_frame();
+        let mut e = Event::new();
+
+        frame(&mut e, &mut src);
+        let b = e.get_mouse(Mouse::Left);
+        assert!(b.down() && b.held());
+        assert!(!b.clicked() && !b.drag_started());
+        assert_eq!((b.click_pos.x, b.click_pos.y), (10, 10));
+
+        frame(&mut e, &mut src);
+        let b = e.get_mouse(Mouse::Left);
+        assert!(b.up() && b.clicked());
+        assert!(!b.held() && !b.drag_started() && !b.drag_ended());
+    }
+
+    #[test]
+    fn drag() {
+        let mut src = ScriptedEventSource::new();
+        src.mouse_down(Mouse::Left, 10, 10)
+            .end_frame()
+            .mouse_move(20, 0)
+            .end_frame()
+            .mouse_move(5, 5)
+            .end_frame()
+            .mouse_up(Mouse::Left)
+            .end_frame();
+        let mut e = Event::new();
+
+        frame(&mut e, &mut src);
+        assert!(!e.get_mouse(Mouse::Left).dragging());
+
+        // The drag starts in the frame the mouse moves past the click distance
+        frame(&mut e, &mut src);
+        let b = e.get_mouse(Mouse::Left);
+        assert!(b.drag_started() && b.dragging() && b.held());
+        assert_eq!((b.drag_pos.x, b.drag_pos.y), (30, 10));
+
+        frame(&mut e, &mut src);
+        let b = e.get_mouse(Mouse::Left);
+        assert!(b.dragging() && !b.drag_started());
+        assert_eq!((b.drag_delta().x, b.drag_delta().y), (25, 5));
+
+        frame(&mut e, &mut src);
+        let b = e.get_mouse(Mouse::Left);
+        assert!(b.drag_ended() && b.up());
+        assert!(!b.clicked() && !b.dragging() && !b.held());
+    }
+
+    #[test]
+    fn drag_within_one_frame() {
+        let mut src = ScriptedEventSource::new();
+        src.mouse_down(Mouse::Left, 10, 10)
+            .mouse_move(0, 20)
+            .mouse_up(Mouse::Left)
+            .end_frame();
+        let mut e = Event::new();
+
+        frame(&mut e, &mut src);
+        let b = e.get_mouse(Mouse::Left);
+        assert!(b.drag_started() && b.drag_ended() && b.up());
+        assert!(!b.clicked());
+    }
+
+    #[test]
+    fn key_press_repeat_release() {
+        let mut src = ScriptedEventSource::new();
+        src.key_down(SPACE)
+            .end_frame()
+            .end_frame()
+            .key_repeat(SPACE)
+            .end_frame()
+            .key_up(SPACE)
+            .end_frame()
+            .end_frame();
+        let mut e = Event::new();
+
+        frame(&mut e, &mut src);
+        let b = e.get_key(SPACE).unwrap();
+        assert!(b.down() && b.held() && b.pressed());
+        assert!(!b.repeated() && !b.up());
+
+        frame(&mut e, &mut src);
+        let b = e.get_key(SPACE).unwrap();
+        assert!(b.held());
+        assert!(!b.down() && !b.pressed());
+        assert_eq!(b.duration, 16);
+
+        // Repeats press the key again without it going down
+        frame(&mut e, &mut src);
+        let b = e.get_key(SPACE).unwrap();
+        assert!(b.repeated() && b.pressed() && b.held());
+        assert!(!b.down());
+
+        frame(&mut e, &mut src);
+        let b = e.get_key(SPACE).unwrap();
+        assert!(b.up());
+        assert!(!b.held() && !b.down());
+
+        frame(&mut e, &mut src);
+        let b = e.get_key(SPACE).unwrap();
+        assert!(!b.up() && !b.held());
+    }
+
+    #[test]
+    fn paste() {
+        let mut src = ScriptedEventSource::new();
+        src.set_text_input(true)
+            .set_clipboard("pasted")
+            .key_down_with(
+                sdl2::SDL_KeyCode::SDLK_v,
+                sdl2::SDL_Keymod::KMOD_LCTRL as u16,
+            )
+            .end_frame();
+        let mut e = Event::new();
+
+        frame(&mut e, &mut src);
+        assert!(e.input_paste);
+        assert_eq!(e.input_text, "pasted");
+    }
+
+    #[test]
+    fn controller() {
+        let a = sdl2::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_A;
+        let left_x = sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTX;
+        let mut src = ScriptedEventSource::new();
+        src.controller_add(3)
+            .controller_button_down(3, a)
+            .controller_axis(3, left_x, i16::MAX)
+            .end_frame()
+            .controller_button_up(3, a)
+            .end_frame()
+            .controller_remove(3)
+            .end_frame();
+        let mut e = Event::new();
+
+        frame(&mut e, &mut src);
+        assert_eq!(e.controllers_added, [3]);
+        let c = e.get_controller(3).unwrap();
+        let b = c.get_button(a).unwrap();
+        assert!(b.down() && b.held() && b.pressed());
+        assert_eq!(c.raw_axis(left_x), 1.0);
+        assert_eq!(c.left_stick().x, 1.0);
+
+        frame(&mut e, &mut src);
+        assert!(e.controllers_added.is_empty());
+        let b = e.get_controller(3).unwrap().get_button(a).unwrap();
+        assert!(b.up() && !b.held());
+
+        frame(&mut e, &mut src);
+        assert_eq!(e.controllers_removed, [3]);
+        assert!(e.get_controller(3).is_none());
+    }
+}
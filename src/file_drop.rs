@@ -0,0 +1,77 @@
+use crate::asset_manager::RenderSystem;
+use crate::rect::Point;
+
+use std::path::Path;
+
+// Extensions of the formats IMG_Init() is called with
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+// Files and text dropped onto the window this frame
+// The items of one drop can arrive over several frames, from began to completed
+#[derive(Clone)]
+pub struct FileDrop {
+    pub files: Vec<String>,
+    pub texts: Vec<String>,
+    // Mouse position at the last drop event, in world and window coordinates
+    pub pos: Point,
+    pub abs_pos: Point,
+    pub began: bool,
+    pub completed: bool,
+    // Between began and completed, kept between frames
+    pub active: bool,
+}
+
+impl FileDrop {
+    pub(crate) fn new() -> Self {
+        FileDrop {
+            files: Vec::new(),
+            texts: Vec::new(),
+            pos: Point { x: 0, y: 0 },
+            abs_pos: Point { x: 0, y: 0 },
+            began: false,
+            completed: false,
+            active: false,
+        }
+    }
+
+    pub(crate) fn reset_frame(&mut self) {
+        self.files.clear();
+        self.texts.clear();
+        self.began = false;
+        self.completed = false;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.texts.is_empty()
+    }
+
+    // Dropped files SDL_image can load, by extension
+    pub fn images(&self) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|f| {
+                Path::new(f)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map_or(false, |ext| {
+                        IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+                    })
+            })
+            .map(|f| f.as_str())
+            .collect()
+    }
+
+    // Load the dropped images into the AssetManager, cached under their path
+    // Returns the paths that loaded, failures are logged by RenderSystem::get_image()
+    pub fn load_images(&self, rs: &mut RenderSystem) -> Vec<String> {
+        let mut loaded = Vec::new();
+        for file in self.images() {
+            rs.get_image(file);
+            // get_image() may have returned the placeholder instead
+            if rs.am.get_image(file).is_some() {
+                loaded.push(file.to_string());
+            }
+        }
+        loaded
+    }
+}
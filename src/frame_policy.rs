@@ -0,0 +1,47 @@
+use crate::event::Event;
+
+// How the main loop behaves while its window is in the background
+#[derive(Copy, Clone)]
+pub struct FramePolicy {
+    pub fps: u32,
+    pub pause_when_minimized: bool,
+    pub pause_when_unfocused: bool,
+    // Frame rate while unfocused, 0 keeps fps
+    pub unfocused_fps: u32,
+    // Frame rate while minimized, nothing is drawn so this only limits polling
+    pub minimized_fps: u32,
+}
+
+impl FramePolicy {
+    pub fn new(fps: u32) -> Self {
+        FramePolicy {
+            fps,
+            pause_when_minimized: true,
+            pause_when_unfocused: false,
+            unfocused_fps: 30,
+            minimized_fps: 10,
+        }
+    }
+
+    // Whether game logic should be skipped this frame
+    pub fn paused(&self, e: &Event) -> bool {
+        (self.pause_when_minimized && e.is_minimized) || (self.pause_when_unfocused && !e.has_focus)
+    }
+
+    // Minimized windows aren't visible so drawing is wasted
+    pub fn should_render(&self, e: &Event) -> bool {
+        !e.is_minimized
+    }
+
+    // Target time for this frame in milliseconds
+    pub fn frame_time(&self, e: &Event) -> u32 {
+        let fps = if e.is_minimized && self.minimized_fps != 0 {
+            self.minimized_fps
+        } else if !e.has_focus && self.unfocused_fps != 0 {
+            self.unfocused_fps
+        } else {
+            self.fps
+        };
+        1000 / fps.max(1)
+    }
+}
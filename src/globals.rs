@@ -1,5 +1,8 @@
 use crate::asset_manager::RenderSystem;
+use crate::audio::AudioSystem;
 
 pub struct Globals {
     pub rs: RenderSystem,
+    // None if no audio device could be opened
+    pub audio: Option<AudioSystem>,
 }
@@ -0,0 +1,250 @@
+use crate::event::{Event, KeyButton, Mouse, MouseButton, NUM_MICE};
+use crate::rect::{Point, PointF};
+use crate::sdl2;
+
+use std::collections::HashSet;
+
+// Input taken by a layer
+#[derive(Clone, Default)]
+struct Consumed {
+    // Bit per Mouse
+    mice: u8,
+    keys: HashSet<sdl2::SDL_KeyCode>,
+    all_keys: bool,
+    scroll: bool,
+    // The mouse is over something on this layer, e.g. a HUD panel
+    hover: bool,
+}
+
+impl Consumed {
+    fn all() -> Self {
+        Consumed {
+            mice: u8::MAX,
+            keys: HashSet::new(),
+            all_keys: true,
+            scroll: true,
+            hover: true,
+        }
+    }
+
+    fn extend(&mut self, other: &Consumed) {
+        self.mice |= other.mice;
+        self.keys.extend(other.keys.iter().copied());
+        self.all_keys |= other.all_keys;
+        self.scroll |= other.scroll;
+        self.hover |= other.hover;
+    }
+
+    fn key(&self, key: sdl2::SDL_KeyCode) -> bool {
+        self.all_keys || self.keys.contains(&key)
+    }
+}
+
+struct Layer {
+    name: String,
+    // Everything is consumed for the layers below, e.g. a modal dialog
+    modal: bool,
+}
+
+// InputStack
+// Layers in priority order, e.g. modal dialog, HUD, world
+// Each frame call update() once, it visits the layers top first
+// A layer sees input consumed by the layers above it as untouched
+pub struct InputStack {
+    // Top first
+    layers: Vec<Layer>,
+    untouched: [MouseButton; NUM_MICE as usize],
+}
+
+impl InputStack {
+    pub fn new() -> Self {
+        InputStack {
+            layers: Vec::new(),
+            untouched: [
+                MouseButton::new(Mouse::Left),
+                MouseButton::new(Mouse::Right),
+                MouseButton::new(Mouse::Middle),
+                MouseButton::new(Mouse::X1),
+                MouseButton::new(Mouse::X2),
+            ],
+        }
+    }
+
+    // Adds a layer on top, replacing a layer with the same name
+    pub fn push(&mut self, name: &str) {
+        self.insert(name, false);
+    }
+
+    // Adds a layer on top that blocks all input to the layers below
+    pub fn push_modal(&mut self, name: &str) {
+        self.insert(name, true);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.layers.retain(|l| l.name != name);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.layers.iter().any(|l| l.name == name)
+    }
+
+    // Layer names, top first
+    pub fn names(&self) -> Vec<&str> {
+        self.layers.iter().map(|l| l.name.as_str()).collect()
+    }
+
+    // Call once per frame after Event::update(), f gets each layer's name and input,
+    // top first, so what a layer consumes is gone for every layer below it
+    pub fn update(&self, e: &Event, mut f: impl FnMut(&str, &mut InputLayer)) {
+        let mut above = Consumed::default();
+        for l in &self.layers {
+            let mut consumed = Consumed::default();
+            f(
+                &l.name,
+                &mut InputLayer {
+                    e,
+                    above: above.clone(),
+                    consumed: &mut consumed,
+                    untouched: &self.untouched,
+                },
+            );
+            if l.modal {
+                above = Consumed::all();
+            } else {
+                above.extend(&consumed);
+            }
+        }
+    }
+
+    fn insert(&mut self, name: &str, modal: bool) {
+        self.remove(name);
+        self.layers.insert(
+            0,
+            Layer {
+                name: name.to_string(),
+                modal,
+            },
+        );
+    }
+}
+
+// InputLayer
+// Event as seen by one layer, mirrors the Event queries
+// Input consumed by a layer above reads as if nothing happened
+pub struct InputLayer<'a> {
+    e: &'a Event,
+    above: Consumed,
+    consumed: &'a mut Consumed,
+    untouched: &'a [MouseButton; NUM_MICE as usize],
+}
+
+impl<'a> InputLayer<'a> {
+    // Unfiltered input, e.g. for window events
+    pub fn event(&self) -> &'a Event {
+        self.e
+    }
+
+    pub fn mouse(&self) -> Point {
+        self.e.mouse
+    }
+
+    pub fn abs_mouse(&self) -> Point {
+        self.e.abs_mouse
+    }
+
+    pub fn get_mouse(&self, button: Mouse) -> &MouseButton {
+        if self.mouse_consumed(button) {
+            &self.untouched[button as usize]
+        } else {
+            self.e.get_mouse(button)
+        }
+    }
+
+    pub fn get_key(&self, key: sdl2::SDL_KeyCode) -> Option<&KeyButton> {
+        if self.above.key(key) {
+            None
+        } else {
+            self.e.get_key(key)
+        }
+    }
+
+    pub fn get_scancode(&self, scancode: sdl2::SDL_Scancode) -> Option<&KeyButton> {
+        self.e
+            .get_scancode(scancode)
+            .filter(|b| !self.above.key(b.key))
+    }
+
+    pub fn scroll(&self) -> i32 {
+        if self.above.scroll {
+            0
+        } else {
+            self.e.scroll
+        }
+    }
+
+    pub fn scroll_x(&self) -> i32 {
+        if self.above.scroll {
+            0
+        } else {
+            self.e.scroll_x
+        }
+    }
+
+    pub fn scroll_precise(&self) -> PointF {
+        if self.above.scroll {
+            PointF { x: 0.0, y: 0.0 }
+        } else {
+            self.e.scroll_precise
+        }
+    }
+
+    pub fn mouse_consumed(&self, button: Mouse) -> bool {
+        self.above.mice & (1 << button as u8) != 0
+    }
+
+    pub fn key_consumed(&self, key: sdl2::SDL_KeyCode) -> bool {
+        self.above.key(key)
+    }
+
+    pub fn scroll_consumed(&self) -> bool {
+        self.above.scroll
+    }
+
+    // The mouse is over a layer above, don't highlight anything under it
+    pub fn hover_consumed(&self) -> bool {
+        self.above.hover
+    }
+
+    // Consumption lasts one frame, consume a held button every frame to keep it
+    pub fn consume_mouse(&mut self, button: Mouse) {
+        self.consumed.mice |= 1 << button as u8;
+    }
+
+    pub fn consume_all_mice(&mut self) {
+        self.consumed.mice = u8::MAX;
+    }
+
+    pub fn consume_key(&mut self, key: sdl2::SDL_KeyCode) {
+        self.consumed.keys.insert(key);
+    }
+
+    // E.g. while a text field has focus
+    pub fn consume_all_keys(&mut self) {
+        self.consumed.all_keys = true;
+    }
+
+    pub fn consume_scroll(&mut self) {
+        self.consumed.scroll = true;
+    }
+
+    pub fn consume_hover(&mut self) {
+        self.consumed.hover = true;
+    }
+
+    // The mouse is over this layer, e.g. a panel: take the mouse buttons, scroll and hover
+    pub fn consume_pointer(&mut self) {
+        self.consume_all_mice();
+        self.consume_scroll();
+        self.consume_hover();
+    }
+}
@@ -0,0 +1,360 @@
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::controller::Controller;
+use crate::error::{Error, Result};
+use crate::event::{Event, Mouse, Status};
+use crate::sdl2;
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{CStr, CString};
+use std::fs;
+
+// A single physical input an action can be bound to
+// Written to config files as "key:Space", "mouse:left" or "button:a"
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Binding {
+    Key(sdl2::SDL_KeyCode),
+    Mouse(Mouse),
+    Button(sdl2::SDL_GameControllerButton),
+}
+
+impl Binding {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (kind, name) = s.split_once(':')?;
+        let cname = CString::new(name).ok()?;
+        match kind {
+            "key" => {
+                let key: Option<sdl2::SDL_KeyCode> =
+                    FromPrimitive::from_i32(unsafe { sdl2::SDL_GetKeyFromName(cname.as_ptr()) });
+                match key {
+                    Some(sdl2::SDL_KeyCode::SDLK_UNKNOWN) | None => None,
+                    Some(k) => Some(Binding::Key(k)),
+                }
+            }
+            "mouse" => match name {
+                "left" => Some(Binding::Mouse(Mouse::Left)),
+                "right" => Some(Binding::Mouse(Mouse::Right)),
+                "middle" => Some(Binding::Mouse(Mouse::Middle)),
+                "x1" => Some(Binding::Mouse(Mouse::X1)),
+                "x2" => Some(Binding::Mouse(Mouse::X2)),
+                _ => None,
+            },
+            "button" => {
+                match unsafe { sdl2::SDL_GameControllerGetButtonFromString(cname.as_ptr()) } {
+                    sdl2::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_INVALID => None,
+                    b => Some(Binding::Button(b)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(k) => format!(
+                "key:{}",
+                cstr_to_string(unsafe { sdl2::SDL_GetKeyName(*k as i32) })
+            ),
+            Binding::Mouse(m) => match m {
+                Mouse::Left => "mouse:left".to_string(),
+                Mouse::Right => "mouse:right".to_string(),
+                Mouse::Middle => "mouse:middle".to_string(),
+                Mouse::X1 => "mouse:x1".to_string(),
+                Mouse::X2 => "mouse:x2".to_string(),
+            },
+            Binding::Button(b) => format!(
+                "button:{}",
+                cstr_to_string(unsafe { sdl2::SDL_GameControllerGetStringForButton(*b) })
+            ),
+        }
+    }
+
+    // Status bits of the bound input, mouse clicks count as presses when the button goes down
+    fn status(&self, e: &Event, controllers: &[&Controller]) -> (u8, u32) {
+        match self {
+            Binding::Key(k) => e.get_key(*k).map_or((0, 0), |kb| (kb.status, kb.duration)),
+            Binding::Mouse(m) => {
+                let mb = e.get_mouse(*m);
+                let mut status = mb.status & !(Status::Pressed as u8);
+                if mb.down() {
+                    status |= Status::Pressed as u8;
+                }
+                (status, mb.duration)
+            }
+            Binding::Button(b) => controllers
+                .iter()
+                .filter_map(|c| c.get_button(*b))
+                .fold((0, 0), |(status, duration), cb| {
+                    (status | cb.status, duration.max(cb.duration))
+                }),
+        }
+    }
+}
+
+fn cstr_to_string(s: *const std::os::raw::c_char) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(s) }.to_string_lossy().to_string()
+}
+
+fn parse_axis(s: &str) -> Option<sdl2::SDL_GameControllerAxis> {
+    let name = s.strip_prefix("axis:")?;
+    let cname = CString::new(name).ok()?;
+    match unsafe { sdl2::SDL_GameControllerGetAxisFromString(cname.as_ptr()) } {
+        sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_INVALID => None,
+        a => Some(a),
+    }
+}
+
+fn axis_name(axis: sdl2::SDL_GameControllerAxis) -> String {
+    format!(
+        "axis:{}",
+        cstr_to_string(unsafe { sdl2::SDL_GameControllerGetStringForAxis(axis) })
+    )
+}
+
+// Combined state of every input bound to an action
+#[derive(Copy, Clone)]
+pub struct ActionState {
+    pub duration: u32,
+    status: u8,
+}
+
+impl ActionState {
+    pub fn down(&self) -> bool {
+        self.status & Status::Down as u8 != 0
+    }
+
+    // Only once every bound input has been released
+    pub fn up(&self) -> bool {
+        self.status & Status::Up as u8 != 0 && !self.held()
+    }
+
+    pub fn held(&self) -> bool {
+        self.status & Status::Held as u8 != 0
+    }
+
+    pub fn pressed(&self) -> bool {
+        self.status & Status::Pressed as u8 != 0
+    }
+
+    // OS auto-repeat of a bound key
+    pub fn repeated(&self) -> bool {
+        self.status & Status::Repeat as u8 != 0
+    }
+}
+
+// Digital inputs push an axis to -1 or 1, analog controller axes are added on top
+#[derive(Clone, Default)]
+pub struct AxisBinding {
+    pub negative: Vec<Binding>,
+    pub positive: Vec<Binding>,
+    pub analog: Vec<sdl2::SDL_GameControllerAxis>,
+}
+
+// Config file layout
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct AxisConfig {
+    negative: Vec<String>,
+    positive: Vec<String>,
+    analog: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct InputConfig {
+    actions: BTreeMap<String, Vec<String>>,
+    axes: BTreeMap<String, AxisConfig>,
+}
+
+pub struct InputMap {
+    actions: HashMap<String, Vec<Binding>>,
+    axes: HashMap<String, AxisBinding>,
+    // Controller to read, None reads every connected controller
+    pub controller: Option<i32>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        InputMap {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+            controller: None,
+        }
+    }
+
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    pub fn clear(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions
+            .get(action)
+            .map(|b| b.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        self.axes.insert(axis.to_string(), binding);
+    }
+
+    pub fn get_axis_binding(&self, axis: &str) -> Option<&AxisBinding> {
+        self.axes.get(axis)
+    }
+
+    pub fn action(&self, e: &Event, action: &str) -> ActionState {
+        let controllers = self.controllers(e);
+        self.bindings(action).iter().fold(
+            ActionState {
+                duration: 0,
+                status: 0,
+            },
+            |state, b| {
+                let (status, duration) = b.status(e, &controllers);
+                ActionState {
+                    duration: state.duration.max(duration),
+                    status: state.status | status,
+                }
+            },
+        )
+    }
+
+    // -1 - 1
+    pub fn axis(&self, e: &Event, axis: &str) -> f32 {
+        let binding = match self.axes.get(axis) {
+            Some(b) => b,
+            None => return 0.0,
+        };
+        let controllers = self.controllers(e);
+        let held = |bindings: &[Binding]| {
+            bindings
+                .iter()
+                .any(|b| b.status(e, &controllers).0 & Status::Held as u8 != 0)
+        };
+        let mut v = 0.0;
+        if held(&binding.negative) {
+            v -= 1.0;
+        }
+        if held(&binding.positive) {
+            v += 1.0;
+        }
+        for a in &binding.analog {
+            for c in &controllers {
+                v += c.axis(*a);
+            }
+        }
+        v.clamp(-1.0, 1.0)
+    }
+
+    fn controllers<'a>(&self, e: &'a Event) -> Vec<&'a Controller> {
+        match self.controller {
+            Some(id) => e.get_controller(id).into_iter().collect(),
+            None => e.controllers.values().collect(),
+        }
+    }
+
+    pub fn load(file: &str) -> Result<Self> {
+        match fs::read_to_string(file) {
+            Ok(text) => Self::parse(&text),
+            Err(e) => Err(Error::new(
+                "InputMap::load()",
+                format!("Unable to read {}: {}", file, e),
+            )),
+        }
+    }
+
+    // Unknown bindings are skipped with a warning so one typo doesn't lose the whole config
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: InputConfig =
+            toml::from_str(text).map_err(|e| Error::new("InputMap::parse()", e.to_string()))?;
+        let parse_all = |names: &[String]| -> Vec<Binding> {
+            names
+                .iter()
+                .filter_map(|name| match Binding::parse(name) {
+                    Some(b) => Some(b),
+                    None => {
+                        println!("InputMap::parse() - Unknown binding {}", name);
+                        None
+                    }
+                })
+                .collect()
+        };
+        let mut map = InputMap::new();
+        for (action, names) in &config.actions {
+            map.actions.insert(action.clone(), parse_all(names));
+        }
+        for (axis, cfg) in &config.axes {
+            map.axes.insert(
+                axis.clone(),
+                AxisBinding {
+                    negative: parse_all(&cfg.negative),
+                    positive: parse_all(&cfg.positive),
+                    analog: cfg
+                        .analog
+                        .iter()
+                        .filter_map(|name| match parse_axis(name) {
+                            Some(a) => Some(a),
+                            None => {
+                                println!("InputMap::parse() - Unknown axis {}", name);
+                                None
+                            }
+                        })
+                        .collect(),
+                },
+            );
+        }
+        Ok(map)
+    }
+
+    pub fn save(&self, file: &str) -> Result<()> {
+        fs::write(file, self.to_toml()?).map_err(|e| {
+            Error::new(
+                "InputMap::save()",
+                format!("Unable to write {}: {}", file, e),
+            )
+        })
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        let names =
+            |bindings: &[Binding]| -> Vec<String> { bindings.iter().map(|b| b.name()).collect() };
+        let config = InputConfig {
+            actions: self
+                .actions
+                .iter()
+                .map(|(action, bindings)| (action.clone(), names(bindings)))
+                .collect(),
+            axes: self
+                .axes
+                .iter()
+                .map(|(axis, b)| {
+                    (
+                        axis.clone(),
+                        AxisConfig {
+                            negative: names(&b.negative),
+                            positive: names(&b.positive),
+                            analog: b.analog.iter().map(|a| axis_name(*a)).collect(),
+                        },
+                    )
+                })
+                .collect(),
+        };
+        toml::to_string(&config).map_err(|e| Error::new("InputMap::to_toml()", e.to_string()))
+    }
+}
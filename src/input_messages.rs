@@ -0,0 +1,298 @@
+use crate::enum_union;
+use crate::enums;
+use crate::event::{Event, Mouse};
+use crate::message;
+use crate::message::{Constructor, MessageBus};
+use crate::sdl2;
+
+// Payloads carry data, so the enum traits are implemented here instead of with enum_type!
+// Messages have to be Copy, text input isn't published and stays on Event
+macro_rules! input_type {
+    ($n: ident) => {
+        impl enums::Eq for $n {}
+
+        impl enums::Stringify for $n {
+            fn to_str(&self) -> &str {
+                stringify!($n)
+            }
+        }
+    };
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KeyInput {
+    Pressed(sdl2::SDL_KeyCode),
+    // OS auto-repeat while held
+    Repeated(sdl2::SDL_KeyCode),
+    Released(sdl2::SDL_KeyCode),
+}
+input_type!(KeyInput);
+
+// Positions are in world coordinates, like Event::mouse
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MouseInput {
+    Pressed {
+        button: Mouse,
+        x: i32,
+        y: i32,
+    },
+    Released {
+        button: Mouse,
+        x: i32,
+        y: i32,
+    },
+    Clicked {
+        button: Mouse,
+        clicks: u8,
+        x: i32,
+        y: i32,
+    },
+    DragStarted {
+        button: Mouse,
+        x: i32,
+        y: i32,
+    },
+    DragEnded {
+        button: Mouse,
+        x: i32,
+        y: i32,
+    },
+    Moved {
+        x: i32,
+        y: i32,
+        dx: i32,
+        dy: i32,
+    },
+    Scrolled {
+        x: f32,
+        y: f32,
+    },
+}
+input_type!(MouseInput);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WindowInput {
+    Quit,
+    CloseRequested,
+    Resized { w: i32, h: i32 },
+    Moved { x: i32, y: i32 },
+    FocusGained,
+    FocusLost,
+    Minimized,
+    Maximized,
+    Restored,
+    MouseEntered,
+    MouseLeft,
+}
+input_type!(WindowInput);
+
+// Controller instance ids
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ControllerInput {
+    Added(i32),
+    Removed(i32),
+}
+input_type!(ControllerInput);
+
+message!(KeyMessage, KeyMessageEnum, KeyInput);
+message!(MouseMessage, MouseMessageEnum, MouseInput);
+message!(WindowMessage, WindowMessageEnum, WindowInput);
+message!(ControllerMessage, ControllerMessageEnum, ControllerInput);
+
+// Send this frame's changes in e to the bus, call once per frame after Event::update()
+// Message types without subscribers are skipped
+pub fn publish_input(bus: &MessageBus, e: &Event) {
+    if bus.has_subscribers::<WindowMessageEnum, WindowMessage>() {
+        publish_window(bus, e);
+    }
+    if bus.has_subscribers::<KeyMessageEnum, KeyMessage>() {
+        publish_keys(bus, e);
+    }
+    if bus.has_subscribers::<MouseMessageEnum, MouseMessage>() {
+        publish_mouse(bus, e);
+    }
+    if bus.has_subscribers::<ControllerMessageEnum, ControllerMessage>() {
+        for id in &e.controllers_added {
+            bus.send_message(ControllerMessage::new(ControllerInput::Added(*id)));
+        }
+        for id in &e.controllers_removed {
+            bus.send_message(ControllerMessage::new(ControllerInput::Removed(*id)));
+        }
+    }
+}
+
+fn publish_window(bus: &MessageBus, e: &Event) {
+    let flags = [
+        (e.focus_gained, WindowInput::FocusGained),
+        (e.focus_lost, WindowInput::FocusLost),
+        (e.minimized, WindowInput::Minimized),
+        (e.maximized, WindowInput::Maximized),
+        (e.restored, WindowInput::Restored),
+        (e.mouse_entered, WindowInput::MouseEntered),
+        (e.mouse_left, WindowInput::MouseLeft),
+        (
+            e.moved,
+            WindowInput::Moved {
+                x: e.window_pos.x,
+                y: e.window_pos.y,
+            },
+        ),
+        (
+            e.resized,
+            WindowInput::Resized {
+                w: e.new_dim.w,
+                h: e.new_dim.h,
+            },
+        ),
+        (e.close_requested, WindowInput::CloseRequested),
+        (e.quit, WindowInput::Quit),
+    ];
+    for (set, msg) in flags {
+        if set {
+            bus.send_message(WindowMessage::new(msg));
+        }
+    }
+}
+
+fn publish_keys(bus: &MessageBus, e: &Event) {
+    // Sorted so subscribers see the same order every run
+    let mut keys: Vec<_> = e.key_buttons.values().collect();
+    keys.sort_by_key(|b| b.key as i32);
+    for b in keys {
+        // A key pressed and repeated in the same frame is both down and repeated
+        if b.down() {
+            bus.send_message(KeyMessage::new(KeyInput::Pressed(b.key)));
+        }
+        if b.repeated() {
+            bus.send_message(KeyMessage::new(KeyInput::Repeated(b.key)));
+        }
+        if b.up() {
+            bus.send_message(KeyMessage::new(KeyInput::Released(b.key)));
+        }
+    }
+}
+
+fn publish_mouse(bus: &MessageBus, e: &Event) {
+    let (x, y) = (e.mouse.x, e.mouse.y);
+    if e.mouse_moved() {
+        bus.send_message(MouseMessage::new(MouseInput::Moved {
+            x,
+            y,
+            dx: e.mouse_delta.x,
+            dy: e.mouse_delta.y,
+        }));
+    }
+    for b in &e.mouse_buttons {
+        let button = b.mouse;
+        if b.down() {
+            bus.send_message(MouseMessage::new(MouseInput::Pressed { button, x, y }));
+        }
+        if b.drag_started() {
+            bus.send_message(MouseMessage::new(MouseInput::DragStarted { button, x, y }));
+        }
+        if b.up() {
+            bus.send_message(MouseMessage::new(MouseInput::Released { button, x, y }));
+        }
+        if b.clicked() {
+            bus.send_message(MouseMessage::new(MouseInput::Clicked {
+                button,
+                clicks: b.clicks,
+                x,
+                y,
+            }));
+        }
+        if b.drag_ended() {
+            bus.send_message(MouseMessage::new(MouseInput::DragEnded { button, x, y }));
+        }
+    }
+    if e.scroll_precise.x != 0.0 || e.scroll_precise.y != 0.0 {
+        bus.send_message(MouseMessage::new(MouseInput::Scrolled {
+            x: e.scroll_precise.x,
+            y: e.scroll_precise.y,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_source::ScriptedEventSource;
+    use crate::rect::{Dimensions, Rect};
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug, PartialEq)]
+    enum Published {
+        Key(KeyInput),
+        Mouse(MouseInput),
+        Window(WindowInput),
+    }
+
+    #[test]
+    fn publish_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = MessageBus::new();
+        let l = log.clone();
+        bus.subscribe::<KeyMessageEnum, KeyMessage>(move |v: &KeyMessageEnum| {
+            let KeyMessageEnum::KeyInput(k) = *v;
+            l.borrow_mut().push(Published::Key(k));
+        });
+        let l = log.clone();
+        bus.subscribe::<MouseMessageEnum, MouseMessage>(move |v: &MouseMessageEnum| {
+            let MouseMessageEnum::MouseInput(m) = *v;
+            l.borrow_mut().push(Published::Mouse(m));
+        });
+        let l = log.clone();
+        bus.subscribe::<WindowMessageEnum, WindowMessage>(move |v: &WindowMessageEnum| {
+            let WindowMessageEnum::WindowInput(w) = *v;
+            l.borrow_mut().push(Published::Window(w));
+        });
+
+        let a = sdl2::SDL_KeyCode::SDLK_a;
+        let mut src = ScriptedEventSource::new();
+        src.quit()
+            .key_down(a)
+            .mouse_down(Mouse::Left, 10, 10)
+            .end_frame()
+            .key_repeat(a)
+            .mouse_up(Mouse::Left)
+            .end_frame()
+            .key_up(a)
+            .wheel(0, 1)
+            .end_frame();
+        let mut e = Event::new();
+        for _ in 0..3 {
+            e.update_from(&mut src, 16, &Rect::new(), &Dimensions { w: 0, h: 0 });
+            publish_input(&bus, &e);
+        }
+
+        let button = Mouse::Left;
+        let (x, y) = (10, 10);
+        assert_eq!(
+            *log.borrow(),
+            [
+                // Window changes first, then keys, then the mouse
+                Published::Window(WindowInput::Quit),
+                Published::Key(KeyInput::Pressed(a)),
+                Published::Mouse(MouseInput::Moved {
+                    x,
+                    y,
+                    dx: 10,
+                    dy: 10
+                }),
+                Published::Mouse(MouseInput::Pressed { button, x, y }),
+                Published::Key(KeyInput::Repeated(a)),
+                Published::Mouse(MouseInput::Released { button, x, y }),
+                Published::Mouse(MouseInput::Clicked {
+                    button,
+                    clicks: 1,
+                    x,
+                    y
+                }),
+                Published::Key(KeyInput::Released(a)),
+                Published::Mouse(MouseInput::Scrolled { x: 0.0, y: -1.0 }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,771 @@
+use num_traits::FromPrimitive;
+
+use crate::bytes::{read_f32, read_i32, read_i64, read_u16, read_u32, read_u8, take};
+use crate::controller::{Controller, ControllerButton, DeadZones};
+use crate::error::{Error, Result};
+use crate::event::{Event, InputSeek, KeyButton, NUM_MICE};
+use crate::event_source::{EventSource, SdlEventSource};
+use crate::rect::{Dimensions, Point, PointF, Rect};
+use crate::sdl2;
+use crate::touch::{Finger, Gesture};
+
+use std::fs;
+use std::io::{BufWriter, Write};
+
+// Recording file layout (all integers little endian):
+//   magic "RGEI"
+//   u16 version
+//   frames until the end of the file:
+//     u32 dt, u16 flags, u16 modifiers
+//     if resized: i32 old w, old h, new w, new h
+//     i32 abs_mouse x, y, mouse_delta x, y, scroll, scroll x, scroll mouse x, y
+//     f32 precise scroll x, y
+//     NUM_MICE mouse buttons: u8 status, u8 clicks, u32 duration, i32 click x, y, drag x, y
+//     u16 key count, keys: i32 key code, i32 scancode, u8 status, u32 duration
+//     u16 scancode count, scancodes in the same layout as keys
+//     u32 text length, text bytes, i32 backspace, delete, move, word move, u8 seek
+//     u32 composition length, composition bytes, i32 composition cursor, length
+//     u16 finger count, fingers: i64 touch id, i64 id, f32 x, y, dx, dy, pressure,
+//       u32 duration, u8 status
+//     gesture: u16 fingers, f32 center x, y, pinch, rotate, pan x, y,
+//       u8 active, f32 last center x, y
+//     u16 dropped file count, files as u32 length and bytes, dropped texts the same way,
+//       i32 drop x, y
+//     u16 added controller count, i32 instance ids, removed controllers the same way
+//     u16 controller count, controllers: i32 instance id, u32 name length, name bytes,
+//       f32 stick and trigger dead zone, NUM_AXES f32 raw axes,
+//       u16 button count, buttons: i32 button, u8 status, u32 duration
+// Bump RECORD_VERSION whenever the frame layout changes
+const RECORD_MAGIC: &[u8; 4] = b"RGEI";
+const RECORD_VERSION: u16 = 8;
+
+const FLAG_QUIT: u16 = 0x01;
+const FLAG_RESIZED: u16 = 0x02;
+const FLAG_RELATIVE_MOUSE: u16 = 0x04;
+const FLAG_COPY: u16 = 0x08;
+const FLAG_CUT: u16 = 0x10;
+const FLAG_PASTE: u16 = 0x20;
+const FLAG_SELECT_ALL: u16 = 0x40;
+const FLAG_SELECT: u16 = 0x80;
+const FLAG_UNDO: u16 = 0x100;
+const FLAG_REDO: u16 = 0x200;
+const FLAG_DROP_BEGAN: u16 = 0x400;
+const FLAG_DROP_COMPLETED: u16 = 0x800;
+const FLAG_DROP_ACTIVE: u16 = 0x1000;
+
+const NUM_AXES: usize = sdl2::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_MAX as usize;
+
+// Input state of one frame
+struct Frame {
+    dt: u32,
+    flags: u16,
+    mods: u16,
+    old_dim: Dimensions,
+    new_dim: Dimensions,
+    abs_mouse: Point,
+    mouse_delta: Point,
+    scroll: i32,
+    scroll_x: i32,
+    scroll_abs_mouse: Point,
+    scroll_precise: PointF,
+    mouse_buttons: Vec<(u8, u8, u32, Point, Point)>,
+    // Only keys with pressed/released/held bits set
+    keys: Vec<RecordedKey>,
+    scancodes: Vec<RecordedKey>,
+    input_text: String,
+    input_backspace: i32,
+    input_delete: i32,
+    input_move: i32,
+    input_word_move: i32,
+    input_seek: u8,
+    input_composition: String,
+    input_composition_cursor: i32,
+    input_composition_len: i32,
+    // World positions aren't stored, they depend on the camera during playback
+    fingers: Vec<Finger>,
+    gesture: Gesture,
+    drop_files: Vec<String>,
+    drop_texts: Vec<String>,
+    drop_abs_pos: Point,
+    controllers_added: Vec<i32>,
+    controllers_removed: Vec<i32>,
+    // Sorted by instance id
+    controllers: Vec<RecordedController>,
+}
+
+impl Frame {
+    fn capture(e: &Event) -> Self {
+        let mut flags = 0;
+        for (set, flag) in [
+            (e.quit, FLAG_QUIT),
+            (e.resized, FLAG_RESIZED),
+            (e.relative_mouse, FLAG_RELATIVE_MOUSE),
+            (e.input_copy, FLAG_COPY),
+            (e.input_cut, FLAG_CUT),
+            (e.input_paste, FLAG_PASTE),
+            (e.input_select_all, FLAG_SELECT_ALL),
+            (e.input_select, FLAG_SELECT),
+            (e.input_undo, FLAG_UNDO),
+            (e.input_redo, FLAG_REDO),
+            (e.file_drop.began, FLAG_DROP_BEGAN),
+            (e.file_drop.completed, FLAG_DROP_COMPLETED),
+            (e.file_drop.active, FLAG_DROP_ACTIVE),
+        ] {
+            if set {
+                flags |= flag;
+            }
+        }
+        Frame {
+            dt: e.dt,
+            flags,
+            mods: e.mods,
+            old_dim: e.old_dim,
+            new_dim: e.new_dim,
+            abs_mouse: e.abs_mouse,
+            mouse_delta: e.mouse_delta,
+            scroll: e.scroll,
+            scroll_x: e.scroll_x,
+            scroll_abs_mouse: e.scroll_abs_mouse,
+            scroll_precise: e.scroll_precise,
+            mouse_buttons: e
+                .mouse_buttons
+                .iter()
+                .map(|b| (b.status, b.clicks, b.duration, b.click_pos, b.drag_pos))
+                .collect(),
+            keys: capture_keys(e.key_buttons.values()),
+            scancodes: capture_keys(e.scan_buttons.values()),
+            input_text: e.input_text.clone(),
+            input_backspace: e.input_backspace,
+            input_delete: e.input_delete,
+            input_move: e.input_move,
+            input_word_move: e.input_word_move,
+            input_seek: e.input_seek as u8,
+            input_composition: e.input_composition.clone(),
+            input_composition_cursor: e.input_composition_cursor,
+            input_composition_len: e.input_composition_len,
+            fingers: e.fingers.clone(),
+            gesture: e.gesture,
+            drop_files: e.file_drop.files.clone(),
+            drop_texts: e.file_drop.texts.clone(),
+            drop_abs_pos: e.file_drop.abs_pos,
+            controllers_added: e.controllers_added.clone(),
+            controllers_removed: e.controllers_removed.clone(),
+            controllers: capture_controllers(e),
+        }
+    }
+
+    // Overwrite the input state of e, window state is left as SDL reports it
+    fn apply(&self, e: &mut Event) {
+        e.dt = self.dt;
+        e.mods = self.mods;
+        e.quit |= self.flags & FLAG_QUIT != 0;
+        e.resized = self.flags & FLAG_RESIZED != 0;
+        if e.resized {
+            e.old_dim = self.old_dim;
+            e.new_dim = self.new_dim;
+        }
+        e.relative_mouse = self.flags & FLAG_RELATIVE_MOUSE != 0;
+        e.input_copy = self.flags & FLAG_COPY != 0;
+        e.input_cut = self.flags & FLAG_CUT != 0;
+        e.input_paste = self.flags & FLAG_PASTE != 0;
+        e.input_select_all = self.flags & FLAG_SELECT_ALL != 0;
+        e.input_select = self.flags & FLAG_SELECT != 0;
+        e.input_undo = self.flags & FLAG_UNDO != 0;
+        e.input_redo = self.flags & FLAG_REDO != 0;
+        e.abs_mouse = self.abs_mouse;
+        e.mouse = e.to_world(self.abs_mouse);
+        e.mouse_delta = self.mouse_delta;
+        e.scroll = self.scroll;
+        e.scroll_x = self.scroll_x;
+        e.scroll_abs_mouse = self.scroll_abs_mouse;
+        e.scroll_mouse = e.to_world(self.scroll_abs_mouse);
+        e.scroll_precise = self.scroll_precise;
+        for (b, (status, clicks, duration, click_pos, drag_pos)) in
+            e.mouse_buttons.iter_mut().zip(&self.mouse_buttons)
+        {
+            b.status = *status;
+            b.clicks = *clicks;
+            b.duration = *duration;
+            b.click_pos = *click_pos;
+            b.drag_pos = *drag_pos;
+        }
+        e.key_buttons = self
+            .keys
+            .iter()
+            .filter_map(|k| k.to_button())
+            .map(|b| (b.key, b))
+            .collect();
+        e.scan_buttons = self
+            .scancodes
+            .iter()
+            .filter_map(|k| k.to_button())
+            .map(|b| (b.scancode, b))
+            .collect();
+        e.input_text = self.input_text.clone();
+        e.input_backspace = self.input_backspace;
+        e.input_delete = self.input_delete;
+        e.input_move = self.input_move;
+        e.input_word_move = self.input_word_move;
+        e.input_seek = match self.input_seek {
+            1 => InputSeek::Start,
+            2 => InputSeek::End,
+            _ => InputSeek::None,
+        };
+        e.input_composition = self.input_composition.clone();
+        e.input_composition_cursor = self.input_composition_cursor;
+        e.input_composition_len = self.input_composition_len;
+        e.fingers = self
+            .fingers
+            .iter()
+            .map(|f| Finger {
+                world: e.touch_to_world(f.pos),
+                ..*f
+            })
+            .collect();
+        e.gesture = self.gesture;
+        e.file_drop.files = self.drop_files.clone();
+        e.file_drop.texts = self.drop_texts.clone();
+        e.file_drop.abs_pos = self.drop_abs_pos;
+        e.file_drop.pos = e.to_world(self.drop_abs_pos);
+        e.file_drop.began = self.flags & FLAG_DROP_BEGAN != 0;
+        e.file_drop.completed = self.flags & FLAG_DROP_COMPLETED != 0;
+        e.file_drop.active = self.flags & FLAG_DROP_ACTIVE != 0;
+        // Replayed controllers have no device, so any open ones are closed
+        e.controllers_added = self.controllers_added.clone();
+        e.controllers_removed = self.controllers_removed.clone();
+        e.controllers = self
+            .controllers
+            .iter()
+            .map(|c| (c.instance_id, c.to_controller()))
+            .collect();
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.dt.to_le_bytes());
+        out.extend_from_slice(&self.flags.to_le_bytes());
+        out.extend_from_slice(&self.mods.to_le_bytes());
+        if self.flags & FLAG_RESIZED != 0 {
+            for v in [
+                self.old_dim.w,
+                self.old_dim.h,
+                self.new_dim.w,
+                self.new_dim.h,
+            ] {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        for v in [
+            self.abs_mouse.x,
+            self.abs_mouse.y,
+            self.mouse_delta.x,
+            self.mouse_delta.y,
+            self.scroll,
+            self.scroll_x,
+            self.scroll_abs_mouse.x,
+            self.scroll_abs_mouse.y,
+        ] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [self.scroll_precise.x, self.scroll_precise.y] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for (status, clicks, duration, click_pos, drag_pos) in &self.mouse_buttons {
+            out.push(*status);
+            out.push(*clicks);
+            out.extend_from_slice(&duration.to_le_bytes());
+            for v in [click_pos.x, click_pos.y, drag_pos.x, drag_pos.y] {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        for keys in [&self.keys, &self.scancodes] {
+            out.extend_from_slice(&(keys.len() as u16).to_le_bytes());
+            for k in keys {
+                out.extend_from_slice(&k.key.to_le_bytes());
+                out.extend_from_slice(&k.scancode.to_le_bytes());
+                out.push(k.status);
+                out.extend_from_slice(&k.duration.to_le_bytes());
+            }
+        }
+        write_string(out, &self.input_text);
+        for v in [
+            self.input_backspace,
+            self.input_delete,
+            self.input_move,
+            self.input_word_move,
+        ] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.push(self.input_seek);
+        write_string(out, &self.input_composition);
+        for v in [self.input_composition_cursor, self.input_composition_len] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(&(self.fingers.len() as u16).to_le_bytes());
+        for f in &self.fingers {
+            out.extend_from_slice(&f.touch_id.to_le_bytes());
+            out.extend_from_slice(&f.id.to_le_bytes());
+            for v in [f.pos.x, f.pos.y, f.delta.x, f.delta.y, f.pressure] {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            out.extend_from_slice(&f.duration.to_le_bytes());
+            out.push(f.status);
+        }
+        let g = &self.gesture;
+        out.extend_from_slice(&g.fingers.to_le_bytes());
+        for v in [g.center.x, g.center.y, g.pinch, g.rotate, g.pan.x, g.pan.y] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        let last = g.last_center.unwrap_or(PointF { x: 0.0, y: 0.0 });
+        out.push(g.last_center.is_some() as u8);
+        for v in [last.x, last.y] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for strings in [&self.drop_files, &self.drop_texts] {
+            out.extend_from_slice(&(strings.len() as u16).to_le_bytes());
+            for s in strings {
+                write_string(out, s);
+            }
+        }
+        for v in [self.drop_abs_pos.x, self.drop_abs_pos.y] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for ids in [&self.controllers_added, &self.controllers_removed] {
+            out.extend_from_slice(&(ids.len() as u16).to_le_bytes());
+            for id in ids {
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+        }
+        out.extend_from_slice(&(self.controllers.len() as u16).to_le_bytes());
+        for c in &self.controllers {
+            c.write(out);
+        }
+    }
+
+    fn read(bytes: &[u8], pos: &mut usize) -> Option<Self> {
+        let dt = read_u32(bytes, pos)?;
+        let flags = read_u16(bytes, pos)?;
+        let mods = read_u16(bytes, pos)?;
+        let (mut old_dim, mut new_dim) = (Dimensions { w: 0, h: 0 }, Dimensions { w: 0, h: 0 });
+        if flags & FLAG_RESIZED != 0 {
+            old_dim.w = read_i32(bytes, pos)?;
+            old_dim.h = read_i32(bytes, pos)?;
+            new_dim.w = read_i32(bytes, pos)?;
+            new_dim.h = read_i32(bytes, pos)?;
+        }
+        let abs_mouse = read_point(bytes, pos)?;
+        let mouse_delta = read_point(bytes, pos)?;
+        let scroll = read_i32(bytes, pos)?;
+        let scroll_x = read_i32(bytes, pos)?;
+        let scroll_abs_mouse = read_point(bytes, pos)?;
+        let scroll_precise = PointF {
+            x: read_f32(bytes, pos)?,
+            y: read_f32(bytes, pos)?,
+        };
+        let mut mouse_buttons = Vec::new();
+        for _ in 0..NUM_MICE {
+            let status = read_u8(bytes, pos)?;
+            let clicks = read_u8(bytes, pos)?;
+            let duration = read_u32(bytes, pos)?;
+            let click_pos = read_point(bytes, pos)?;
+            mouse_buttons.push((status, clicks, duration, click_pos, read_point(bytes, pos)?));
+        }
+        let keys = read_keys(bytes, pos)?;
+        let scancodes = read_keys(bytes, pos)?;
+        let input_text = read_string(bytes, pos)?;
+        Some(Frame {
+            dt,
+            flags,
+            mods,
+            old_dim,
+            new_dim,
+            abs_mouse,
+            mouse_delta,
+            scroll,
+            scroll_x,
+            scroll_abs_mouse,
+            scroll_precise,
+            mouse_buttons,
+            keys,
+            scancodes,
+            input_text,
+            input_backspace: read_i32(bytes, pos)?,
+            input_delete: read_i32(bytes, pos)?,
+            input_move: read_i32(bytes, pos)?,
+            input_word_move: read_i32(bytes, pos)?,
+            input_seek: read_u8(bytes, pos)?,
+            input_composition: read_string(bytes, pos)?,
+            input_composition_cursor: read_i32(bytes, pos)?,
+            input_composition_len: read_i32(bytes, pos)?,
+            fingers: read_fingers(bytes, pos)?,
+            gesture: read_gesture(bytes, pos)?,
+            drop_files: read_strings(bytes, pos)?,
+            drop_texts: read_strings(bytes, pos)?,
+            drop_abs_pos: read_point(bytes, pos)?,
+            controllers_added: read_ids(bytes, pos)?,
+            controllers_removed: read_ids(bytes, pos)?,
+            controllers: read_controllers(bytes, pos)?,
+        })
+    }
+}
+
+struct RecordedKey {
+    key: i32,
+    scancode: i32,
+    status: u8,
+    duration: u32,
+}
+
+impl RecordedKey {
+    fn to_button(&self) -> Option<KeyButton> {
+        let mut b = KeyButton::new(
+            FromPrimitive::from_i32(self.key)?,
+            FromPrimitive::from_i32(self.scancode)?,
+        );
+        b.status = self.status;
+        b.duration = self.duration;
+        Some(b)
+    }
+}
+
+fn capture_keys<'a>(buttons: impl Iterator<Item = &'a KeyButton>) -> Vec<RecordedKey> {
+    buttons
+        .filter(|b| b.status != 0)
+        .map(|b| RecordedKey {
+            key: b.key as i32,
+            scancode: b.scancode as i32,
+            status: b.status,
+            duration: b.duration,
+        })
+        .collect()
+}
+
+fn read_keys(bytes: &[u8], pos: &mut usize) -> Option<Vec<RecordedKey>> {
+    let count = read_u16(bytes, pos)?;
+    let mut keys = Vec::new();
+    for _ in 0..count {
+        keys.push(RecordedKey {
+            key: read_i32(bytes, pos)?,
+            scancode: read_i32(bytes, pos)?,
+            status: read_u8(bytes, pos)?,
+            duration: read_u32(bytes, pos)?,
+        });
+    }
+    Some(keys)
+}
+
+struct RecordedController {
+    instance_id: i32,
+    name: String,
+    dead_zones: DeadZones,
+    axes: Vec<f32>,
+    // Sorted by button
+    buttons: Vec<ControllerButton>,
+}
+
+impl RecordedController {
+    fn capture(c: &Controller) -> Self {
+        let mut buttons: Vec<ControllerButton> = c.buttons().copied().collect();
+        buttons.sort_by_key(|b| b.button as i32);
+        RecordedController {
+            instance_id: c.instance_id,
+            name: c.name.clone(),
+            dead_zones: c.dead_zones,
+            axes: c.raw_axes().to_vec(),
+            buttons,
+        }
+    }
+
+    fn to_controller(&self) -> Controller {
+        let mut c = Controller::detached(self.instance_id, &self.name, self.dead_zones);
+        for (i, v) in self.axes.iter().enumerate() {
+            c.set_raw_axis(i, *v);
+        }
+        for b in &self.buttons {
+            c.set_button(*b);
+        }
+        c
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.instance_id.to_le_bytes());
+        write_string(out, &self.name);
+        for v in [self.dead_zones.stick, self.dead_zones.trigger] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in &self.axes {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(&(self.buttons.len() as u16).to_le_bytes());
+        for b in &self.buttons {
+            out.extend_from_slice(&(b.button as i32).to_le_bytes());
+            out.push(b.status);
+            out.extend_from_slice(&b.duration.to_le_bytes());
+        }
+    }
+
+    fn read(bytes: &[u8], pos: &mut usize) -> Option<Self> {
+        let instance_id = read_i32(bytes, pos)?;
+        let name = read_string(bytes, pos)?;
+        let dead_zones = DeadZones {
+            stick: read_f32(bytes, pos)?,
+            trigger: read_f32(bytes, pos)?,
+        };
+        let mut axes = Vec::new();
+        for _ in 0..NUM_AXES {
+            axes.push(read_f32(bytes, pos)?);
+        }
+        let count = read_u16(bytes, pos)?;
+        let mut buttons = Vec::new();
+        for _ in 0..count {
+            buttons.push(ControllerButton {
+                button: FromPrimitive::from_i32(read_i32(bytes, pos)?)?,
+                status: read_u8(bytes, pos)?,
+                duration: read_u32(bytes, pos)?,
+            });
+        }
+        Some(RecordedController {
+            instance_id,
+            name,
+            dead_zones,
+            axes,
+            buttons,
+        })
+    }
+}
+
+fn capture_controllers(e: &Event) -> Vec<RecordedController> {
+    let mut controllers: Vec<RecordedController> = e
+        .controllers
+        .values()
+        .map(RecordedController::capture)
+        .collect();
+    controllers.sort_by_key(|c| c.instance_id);
+    controllers
+}
+
+fn read_controllers(bytes: &[u8], pos: &mut usize) -> Option<Vec<RecordedController>> {
+    let count = read_u16(bytes, pos)?;
+    let mut controllers = Vec::new();
+    for _ in 0..count {
+        controllers.push(RecordedController::read(bytes, pos)?);
+    }
+    Some(controllers)
+}
+
+fn read_ids(bytes: &[u8], pos: &mut usize) -> Option<Vec<i32>> {
+    let count = read_u16(bytes, pos)?;
+    let mut ids = Vec::new();
+    for _ in 0..count {
+        ids.push(read_i32(bytes, pos)?);
+    }
+    Some(ids)
+}
+
+fn read_fingers(bytes: &[u8], pos: &mut usize) -> Option<Vec<Finger>> {
+    let count = read_u16(bytes, pos)?;
+    let mut fingers = Vec::new();
+    for _ in 0..count {
+        let touch_id = read_i64(bytes, pos)?;
+        let id = read_i64(bytes, pos)?;
+        let p = read_point_f(bytes, pos)?;
+        let mut f = Finger::new(touch_id, id, p, 0.0);
+        f.delta = read_point_f(bytes, pos)?;
+        f.pressure = read_f32(bytes, pos)?;
+        f.duration = read_u32(bytes, pos)?;
+        f.status = read_u8(bytes, pos)?;
+        fingers.push(f);
+    }
+    Some(fingers)
+}
+
+fn read_gesture(bytes: &[u8], pos: &mut usize) -> Option<Gesture> {
+    let mut g = Gesture::new();
+    g.fingers = read_u16(bytes, pos)?;
+    g.center = read_point_f(bytes, pos)?;
+    g.pinch = read_f32(bytes, pos)?;
+    g.rotate = read_f32(bytes, pos)?;
+    g.pan = read_point_f(bytes, pos)?;
+    let active = read_u8(bytes, pos)? != 0;
+    let last = read_point_f(bytes, pos)?;
+    g.last_center = if active { Some(last) } else { None };
+    Some(g)
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn read_string(bytes: &[u8], pos: &mut usize) -> Option<String> {
+    let len = read_u32(bytes, pos)? as usize;
+    String::from_utf8(take(bytes, pos, len)?.to_vec()).ok()
+}
+
+fn read_strings(bytes: &[u8], pos: &mut usize) -> Option<Vec<String>> {
+    let count = read_u16(bytes, pos)?;
+    let mut strings = Vec::new();
+    for _ in 0..count {
+        strings.push(read_string(bytes, pos)?);
+    }
+    Some(strings)
+}
+
+fn read_point(bytes: &[u8], pos: &mut usize) -> Option<Point> {
+    Some(Point {
+        x: read_i32(bytes, pos)?,
+        y: read_i32(bytes, pos)?,
+    })
+}
+
+fn read_point_f(bytes: &[u8], pos: &mut usize) -> Option<PointF> {
+    Some(PointF {
+        x: read_f32(bytes, pos)?,
+        y: read_f32(bytes, pos)?,
+    })
+}
+
+// InputRecorder
+// Call record() once per frame, after Event::update() and before game logic reads the event
+pub struct InputRecorder {
+    out: BufWriter<fs::File>,
+    buf: Vec<u8>,
+    frames: u32,
+}
+
+impl InputRecorder {
+    pub fn create(file: &str) -> Result<Self> {
+        let f = fs::File::create(file).map_err(|e| {
+            Error::new(
+                "InputRecorder::create()",
+                format!("Unable to create {}: {}", file, e),
+            )
+        })?;
+        let mut recorder = InputRecorder {
+            out: BufWriter::new(f),
+            buf: Vec::new(),
+            frames: 0,
+        };
+        recorder.buf.extend_from_slice(RECORD_MAGIC);
+        recorder
+            .buf
+            .extend_from_slice(&RECORD_VERSION.to_le_bytes());
+        recorder.write_buf("InputRecorder::create()")?;
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, e: &Event) -> Result<()> {
+        Frame::capture(e).write(&mut self.buf);
+        self.frames += 1;
+        self.write_buf("InputRecorder::record()")
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    // Also done on drop, but errors are lost there
+    pub fn flush(&mut self) -> Result<()> {
+        self.out
+            .flush()
+            .map_err(|e| Error::new("InputRecorder::flush()", e.to_string()))
+    }
+
+    fn write_buf(&mut self, context: &'static str) -> Result<()> {
+        let res = self.out.write_all(&self.buf);
+        self.buf.clear();
+        res.map_err(|e| Error::new(context, e.to_string()))
+    }
+}
+
+// InputPlayer
+// Replays a recording in place of Event::update(), game logic must use Event::dt for
+// timing so replays match the recorded run frame for frame
+pub struct InputPlayer {
+    frames: Vec<Frame>,
+    frame: usize,
+}
+
+impl InputPlayer {
+    // The whole file is checked up front so a bad recording fails before playback starts
+    pub fn open(file: &str) -> Result<Self> {
+        let bytes = fs::read(file).map_err(|e| {
+            Error::new(
+                "InputPlayer::open()",
+                format!("Unable to read {}: {}", file, e),
+            )
+        })?;
+        let mut pos = 0;
+        if take(&bytes, &mut pos, RECORD_MAGIC.len()) != Some(&RECORD_MAGIC[..]) {
+            return Err(Error::new(
+                "InputPlayer::open()",
+                format!("{} is not an input recording", file),
+            ));
+        }
+        let version = read_u16(&bytes, &mut pos);
+        if version != Some(RECORD_VERSION) {
+            return Err(Error::new(
+                "InputPlayer::open()",
+                format!(
+                    "{} has recording version {:?}, expected {}",
+                    file, version, RECORD_VERSION
+                ),
+            ));
+        }
+        let mut frames = Vec::new();
+        while pos < bytes.len() {
+            match Frame::read(&bytes, &mut pos) {
+                Some(f) => frames.push(f),
+                None => {
+                    return Err(Error::new(
+                        "InputPlayer::open()",
+                        format!("{} is corrupt at frame {}", file, frames.len()),
+                    ))
+                }
+            }
+        }
+        Ok(InputPlayer { frames, frame: 0 })
+    }
+
+    // Advance one frame, returns false once every frame has been played
+    // SDL events are still polled so window events and quitting keep working
+    pub fn update(&mut self, e: &mut Event, camera: &Rect, screen: &Dimensions) -> bool {
+        let frame = match self.frames.get(self.frame) {
+            Some(f) => f,
+            None => return false,
+        };
+        self.frame += 1;
+        e.set_view(camera, screen);
+        let mut src = SdlEventSource::new();
+        e.begin_update(frame.dt, &src);
+        while let Some(event) = src.poll() {
+            match FromPrimitive::from_u32(unsafe { event.type_ }) {
+                Some(sdl2::SDL_EventType::SDL_QUIT)
+                | Some(sdl2::SDL_EventType::SDL_WINDOWEVENT) => {
+                    if e.accepts(&event) {
+                        e.update_event(&event, &src);
+                    }
+                }
+                _ => {}
+            }
+        }
+        frame.apply(e);
+        true
+    }
+
+    // Number of frames played so far
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame >= self.frames.len()
+    }
+}
@@ -0,0 +1,111 @@
+use crate::event::Event;
+use crate::input_map::InputMap;
+use crate::sdl2;
+
+use std::collections::HashMap;
+
+const DEFAULT_DELAY: u32 = 400;
+const DEFAULT_RATE: u32 = 10;
+
+// Repeat timing in milliseconds
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RepeatConfig {
+    // Time held before the first repeat
+    pub delay: u32,
+    // Time between repeats
+    pub interval: u32,
+}
+
+impl RepeatConfig {
+    // rate is in repeats per second
+    pub fn new(delay: u32, rate: u32) -> Self {
+        RepeatConfig {
+            delay,
+            interval: 1000 / rate.max(1),
+        }
+    }
+
+    // Repeats reached after being held for t
+    fn repeats_at(&self, t: u32) -> u32 {
+        if t < self.delay {
+            0
+        } else {
+            1 + (t - self.delay) / self.interval.max(1)
+        }
+    }
+
+    // Presses this frame of a button that has been held for duration, including the
+    // initial press, derived from the held time only so it doesn't depend on the OS
+    pub fn presses(&self, down: bool, held: bool, duration: u32, dt: u32) -> u32 {
+        if down {
+            return 1;
+        }
+        if !held {
+            return 0;
+        }
+        self.repeats_at(duration) - self.repeats_at(duration.saturating_sub(dt))
+    }
+}
+
+impl Default for RepeatConfig {
+    fn default() -> Self {
+        RepeatConfig::new(DEFAULT_DELAY, DEFAULT_RATE)
+    }
+}
+
+// KeyRepeat
+// Engine controlled repeat for keys and actions, e.g. for menu navigation
+// OS repeats are ignored, a held key repeats at the same rate on every machine
+pub struct KeyRepeat {
+    pub default: RepeatConfig,
+    keys: HashMap<sdl2::SDL_KeyCode, RepeatConfig>,
+    actions: HashMap<String, RepeatConfig>,
+}
+
+impl KeyRepeat {
+    pub fn new(default: RepeatConfig) -> Self {
+        KeyRepeat {
+            default,
+            keys: HashMap::new(),
+            actions: HashMap::new(),
+        }
+    }
+
+    pub fn set_key(&mut self, key: sdl2::SDL_KeyCode, config: RepeatConfig) {
+        self.keys.insert(key, config);
+    }
+
+    pub fn set_action(&mut self, action: &str, config: RepeatConfig) {
+        self.actions.insert(action.to_string(), config);
+    }
+
+    pub fn key_config(&self, key: sdl2::SDL_KeyCode) -> RepeatConfig {
+        self.keys.get(&key).copied().unwrap_or(self.default)
+    }
+
+    pub fn action_config(&self, action: &str) -> RepeatConfig {
+        self.actions.get(action).copied().unwrap_or(self.default)
+    }
+
+    // Presses this frame, usually 0 or 1 but more if a frame took longer than the interval
+    pub fn key(&self, e: &Event, key: sdl2::SDL_KeyCode) -> u32 {
+        e.get_key(key).map_or(0, |b| {
+            self.key_config(key)
+                .presses(b.down(), b.held(), b.duration, e.dt)
+        })
+    }
+
+    pub fn key_fired(&self, e: &Event, key: sdl2::SDL_KeyCode) -> bool {
+        self.key(e, key) > 0
+    }
+
+    pub fn action(&self, e: &Event, map: &InputMap, action: &str) -> u32 {
+        let state = map.action(e, action);
+        self.action_config(action)
+            .presses(state.down(), state.held(), state.duration, e.dt)
+    }
+
+    pub fn action_fired(&self, e: &Event, map: &InputMap, action: &str) -> bool {
+        self.action(e, map, action) > 0
+    }
+}
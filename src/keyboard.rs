@@ -0,0 +1,89 @@
+use num_traits::FromPrimitive;
+
+use crate::sdl2;
+
+use std::ffi::CString;
+
+// Modifier keys, either side counts
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Mod {
+    Shift,
+    Ctrl,
+    Alt,
+    // Cmd on macOS, the Windows key elsewhere
+    Gui,
+}
+
+impl Mod {
+    pub const ALL: [Mod; 4] = [Mod::Shift, Mod::Ctrl, Mod::Alt, Mod::Gui];
+
+    // SDL_Keymod bits of both sides
+    pub fn mask(&self) -> u16 {
+        (match self {
+            Mod::Shift => sdl2::SDL_Keymod::KMOD_SHIFT,
+            Mod::Ctrl => sdl2::SDL_Keymod::KMOD_CTRL,
+            Mod::Alt => sdl2::SDL_Keymod::KMOD_ALT,
+            Mod::Gui => sdl2::SDL_Keymod::KMOD_GUI,
+        }) as u16
+    }
+
+    pub fn held(&self, mods: u16) -> bool {
+        mods & self.mask() != 0
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "shift" => Some(Mod::Shift),
+            "ctrl" | "control" => Some(Mod::Ctrl),
+            "alt" | "option" => Some(Mod::Alt),
+            "gui" | "cmd" | "super" | "win" => Some(Mod::Gui),
+            _ => None,
+        }
+    }
+}
+
+// A key with an exact set of modifiers, e.g. Ctrl+Shift+Z
+// Lock keys such as Caps Lock are ignored
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Chord {
+    pub mods: Vec<Mod>,
+    pub key: sdl2::SDL_KeyCode,
+}
+
+impl Chord {
+    pub fn new(mods: &[Mod], key: sdl2::SDL_KeyCode) -> Self {
+        Chord {
+            mods: mods.to_vec(),
+            key,
+        }
+    }
+
+    // "Ctrl+Shift+Z", key names are SDL key names
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = s.split('+').map(|p| p.trim()).collect();
+        // "Ctrl++" binds the plus key
+        if s.ends_with("++") {
+            parts.truncate(parts.len().saturating_sub(2));
+            parts.push("+");
+        }
+        let key_name = parts.pop()?;
+        let mut mods = Vec::new();
+        for p in parts {
+            mods.push(Mod::parse(p)?);
+        }
+        let cname = CString::new(key_name).ok()?;
+        let key: Option<sdl2::SDL_KeyCode> =
+            FromPrimitive::from_i32(unsafe { sdl2::SDL_GetKeyFromName(cname.as_ptr()) });
+        match key {
+            Some(sdl2::SDL_KeyCode::SDLK_UNKNOWN) | None => None,
+            Some(key) => Some(Chord { mods, key }),
+        }
+    }
+
+    // Exactly the chord's modifiers are held, extra modifiers don't match
+    pub fn mods_match(&self, mods: u16) -> bool {
+        Mod::ALL
+            .iter()
+            .all(|m| m.held(mods) == self.mods.contains(m))
+    }
+}
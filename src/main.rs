@@ -7,24 +7,70 @@ use sdl2_bindings::sdl2_ as sdl2;
 mod sdl2_image_bindings;
 use sdl2_image_bindings::sdl2_image_ as sdl2_image;
 
+mod sdl2_mixer_bindings;
+use sdl2_mixer_bindings::sdl2_mixer_ as sdl2_mixer;
+
 mod asset_manager;
 use asset_manager::RenderSystem;
 
+mod asset_pack;
+mod bytes;
+mod clipboard;
+mod combo;
+mod controller;
+mod cursor;
+mod display;
+mod manifest;
+
+mod audio;
+use audio::{AudioConfig, AudioSystem};
+
 mod pointers;
 use pointers::*;
 
+mod error;
+
+mod enums;
+mod message;
+#[cfg(test)]
+mod test;
+
 mod globals;
 use globals::Globals;
 
 mod event;
 use event::Event;
+mod event_router;
+mod event_source;
+mod file_drop;
+mod frame_policy;
+mod input_context;
+mod input_map;
+mod input_messages;
+mod input_record;
+mod key_repeat;
+mod keyboard;
+use frame_policy::FramePolicy;
+use input_record::{InputPlayer, InputRecorder};
 mod rect;
+mod text_edit;
+mod touch;
 use rect::{Dimensions, Rect};
 
 const FPS: u32 = 60;
-const FRAME_TIME: u32 = 1000 / FPS;
 
 fn main() {
+    // --record <file> saves this run's input, --replay <file> plays it back
+    let args: Vec<String> = std::env::args().collect();
+    let arg_value = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+    let record_file = arg_value("--record");
+    let replay_file = arg_value("--replay");
+
     // Initialize SDL2
     if unsafe { sdl2::SDL_Init(sdl2::SDL_INIT_EVERYTHING) } == 0 {
         println!("SDL Initialized");
@@ -38,15 +84,57 @@ fn main() {
     } else {
         eprintln!("SDL_Image Failed to Initialize");
     }
+    let mix_init_flags = sdl2_mixer::MIX_InitFlags::MIX_INIT_OGG as i32
+        | sdl2_mixer::MIX_InitFlags::MIX_INIT_MP3 as i32;
+    if unsafe { sdl2_mixer::Mix_Init(mix_init_flags) } & mix_init_flags == mix_init_flags {
+        println!("SDL_Mixer Initialized");
+    } else {
+        eprintln!("SDL_Mixer Failed to Initialize");
+    }
 
     let w = 960;
     let h = 720;
     let img_w = 100;
 
-    // Create a window
-    let mut globals = Globals {
-        rs: RenderSystem::new(Window::new().title("Game Engine").dimensions(w, h)),
+    // Create a window and open audio, falling back to silence without a sound device
+    // and running without audio if even that fails
+    let globals = Window::builder()
+        .title("Game Engine")
+        .dimensions(w, h)
+        .build()
+        .and_then(RenderSystem::new)
+        .map(|rs| {
+            let audio = AudioSystem::new(AudioConfig::default()).or_else(|e| {
+                eprintln!("{}", e);
+                AudioSystem::new(AudioConfig {
+                    driver: Some("dummy"),
+                    ..AudioConfig::default()
+                })
+            });
+            let audio = match audio {
+                Ok(audio) => Some(audio),
+                Err(e) => {
+                    eprintln!("Continuing without audio: {}", e);
+                    None
+                }
+            };
+            Globals { rs, audio }
+        });
+    let mut globals = match globals {
+        Ok(globals) => globals,
+        Err(e) => {
+            eprintln!("{}", e);
+            unsafe {
+                sdl2_mixer::Mix_Quit();
+                sdl2_image::IMG_Quit();
+                sdl2::SDL_Quit();
+            }
+            return;
+        }
     };
+    if let Err(e) = globals.rs.enable_placeholder() {
+        eprintln!("{}", e);
+    }
 
     let screen = Dimensions { w, h };
     let camera = Rect {
@@ -57,13 +145,31 @@ fn main() {
     };
 
     let tex = globals.rs.get_image("res/bra_vector.png");
-    let mut rect = Rect {
-        x: (w - img_w) as f32 / 2.0,
-        y: (h - img_w) as f32 / 2.0,
-        w: img_w as f32,
-        h: img_w as f32,
-    };
+    let mut rect = Rect::from_width(
+        camera.cx(),
+        camera.cy(),
+        img_w as f32,
+        tex.as_ref().map_or(Dimensions { w: 1, h: 1 }, |t| t.size()),
+        rect::Align::Center,
+        rect::Align::Center,
+    );
+
+    let mut recorder = record_file.and_then(|file| match InputRecorder::create(&file) {
+        Ok(r) => Some(r),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    });
+    let mut player = replay_file.and_then(|file| match InputPlayer::open(&file) {
+        Ok(p) => Some(p),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    });
 
+    let frame_policy = FramePolicy::new(FPS);
     let mut event = Event::new();
     let mut t = unsafe { sdl2::SDL_GetTicks() };
     let mut dt;
@@ -71,39 +177,47 @@ fn main() {
         dt = unsafe { sdl2::SDL_GetTicks() } - t;
         t += dt;
 
-        event.update(dt, &camera, &screen);
-
-        match event.get_key(sdl2::SDL_KeyCode::SDLK_SPACE) {
-            Some(kb) => {
-                if kb.held() {
-                    println!("_ {}", kb.duration)
+        match &mut player {
+            Some(p) => {
+                if !p.update(&mut event, &camera, &screen) {
+                    println!("Replay finished after {} frames", p.frame());
+                    break;
                 }
             }
-            None => (),
+            None => event.update(dt, &camera, &screen),
+        }
+        if let Some(r) = &mut recorder {
+            if let Err(e) = r.record(&event) {
+                eprintln!("{}", e);
+                recorder = None;
+            }
         }
 
-        let l = event.get_mouse(event::Mouse::Left);
-        if l.clicked() {
-            rect.set_pos(
-                l.click_pos.x as f32,
-                l.click_pos.y as f32,
-                rect::Align::Center,
-                rect::Align::Center,
-            );
-            rect.fit_within(&camera);
+        if !frame_policy.paused(&event) {
+            update(&event, &camera, &mut rect);
         }
 
-        // Clear the screen
-        globals.rs.r.clear();
+        if frame_policy.should_render(&event) {
+            // Clear the screen
+            globals.rs.r.clear();
 
-        draw!(globals.rs, tex, std::ptr::null(), &rect.to_sdl_rect());
+            draw!(globals.rs, tex, std::ptr::null(), &rect.to_sdl_rect());
 
-        // Update the screen
-        globals.rs.r.present();
+            // Update the screen
+            globals.rs.r.present();
+        }
 
+        let frame_time = frame_policy.frame_time(&event);
         dt = unsafe { sdl2::SDL_GetTicks() } - t;
-        if dt < FRAME_TIME {
-            unsafe { sdl2::SDL_Delay(FRAME_TIME - dt) };
+        if dt < frame_time {
+            unsafe { sdl2::SDL_Delay(frame_time - dt) };
+        }
+    }
+
+    if let Some(r) = &mut recorder {
+        match r.flush() {
+            Ok(()) => println!("Recorded {} frames", r.frames()),
+            Err(e) => eprintln!("{}", e),
         }
     }
 
@@ -112,7 +226,30 @@ fn main() {
 
     // Destroy the window and quit SDL2
     unsafe {
+        sdl2_mixer::Mix_Quit();
         sdl2_image::IMG_Quit();
         sdl2::SDL_Quit();
     }
 }
+
+fn update(event: &Event, camera: &Rect, rect: &mut Rect) {
+    match event.get_key(sdl2::SDL_KeyCode::SDLK_SPACE) {
+        Some(kb) => {
+            if kb.held() {
+                println!("_ {}", kb.duration)
+            }
+        }
+        None => (),
+    }
+
+    let l = event.get_mouse(event::Mouse::Left);
+    if l.clicked() {
+        rect.set_pos(
+            l.click_pos.x as f32,
+            l.click_pos.y as f32,
+            rect::Align::Center,
+            rect::Align::Center,
+        );
+        rect.fit_within(camera);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::error::{Error, Result};
+
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::fs;
+
+// A named set of assets that are loaded and released together
+//
+// [groups.level1]
+// images = ["res/bra_vector.png", "res/pump.jpg"]
+// atlases = ["res/tiles.png"]
+// sounds = ["res/jump.wav"]
+// music = ["res/theme.ogg"]
+// Unknown keys are an error, so a misspelled key doesn't skip its files
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssetGroup {
+    pub images: Vec<String>,
+    // Sprite sheets, loaded as images
+    pub atlases: Vec<String>,
+    // Not supported yet, preloading a group with fonts fails
+    pub fonts: Vec<String>,
+    pub sounds: Vec<String>,
+    pub music: Vec<String>,
+}
+
+impl AssetGroup {
+    pub fn files(&self) -> impl Iterator<Item = &String> {
+        self.images
+            .iter()
+            .chain(self.atlases.iter())
+            .chain(self.fonts.iter())
+            .chain(self.sounds.iter())
+            .chain(self.music.iter())
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct AssetManifest {
+    #[serde(default)]
+    groups: HashMap<String, AssetGroup>,
+}
+
+impl AssetManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(file: &str) -> Result<Self> {
+        match fs::read_to_string(file) {
+            Ok(text) => Self::parse(&text),
+            Err(e) => Err(Error::new(
+                "AssetManifest::load()",
+                format!("Unable to read {}: {}", file, e),
+            )),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| Error::new("AssetManifest::parse()", e.to_string()))
+    }
+
+    // Merge the groups of another manifest into this one
+    pub fn extend(&mut self, other: AssetManifest) {
+        self.groups.extend(other.groups);
+    }
+
+    pub fn get_group(&self, name: &str) -> Option<&AssetGroup> {
+        self.groups.get(name)
+    }
+
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(|name| name.as_str())
+    }
+}
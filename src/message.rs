@@ -4,6 +4,10 @@ use std::marker::PhantomData;
 use crate::enum_union;
 use crate::enums;
 use crate::enums::New;
+use crate::input_messages::{
+    ControllerMessageEnum, KeyMessageEnum, MouseMessageEnum, WindowMessageEnum,
+};
+#[cfg(test)]
 use crate::test::{MyMessageEnum, OtherMessageEnum};
 
 // Message extensions
@@ -71,7 +75,26 @@ macro_rules! message {
     };
 }
 
-enum_union!(Master, MyMessageEnum, OtherMessageEnum);
+#[cfg(not(test))]
+enum_union!(
+    Master,
+    KeyMessageEnum,
+    MouseMessageEnum,
+    WindowMessageEnum,
+    ControllerMessageEnum
+);
+
+// The messages of test.rs only exist in test builds
+#[cfg(test)]
+enum_union!(
+    Master,
+    MyMessageEnum,
+    OtherMessageEnum,
+    KeyMessageEnum,
+    MouseMessageEnum,
+    WindowMessageEnum,
+    ControllerMessageEnum
+);
 
 // Subscription traits
 pub trait SubscriptionHandleTrait {
@@ -166,6 +189,10 @@ impl MessageBus {
         }
     }
 
+    pub fn has_subscribers<U, T: MessageTrait<U>>(&self) -> bool {
+        self.subs.get(T::NAME).map_or(false, |v| !v.is_empty())
+    }
+
     pub fn send_message<U, T: MessageTrait<U>>(&self, msg: T)
     where
         Master: enums::New<U>,
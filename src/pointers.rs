@@ -1,44 +1,428 @@
+use num_traits::FromPrimitive;
+
+use crate::error::{Error, Result};
+use crate::rect::{Dimensions, DimensionsF};
 use crate::sdl2;
 use crate::sdl2_image;
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::ptr::NonNull;
+use std::rc::{Rc, Weak};
 
 // Window
-pub struct Window {
-    w: NonNull<sdl2::SDL_Window>,
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Fullscreen {
+    Off,
+    // Changes the display mode to the window size
+    Exclusive,
+    // Covers the desktop at its current resolution
+    Desktop,
 }
 
-impl Window {
+impl Fullscreen {
+    fn flags(&self) -> u32 {
+        match self {
+            Fullscreen::Off => 0,
+            Fullscreen::Exclusive => sdl2::SDL_WindowFlags::SDL_WINDOW_FULLSCREEN as u32,
+            Fullscreen::Desktop => sdl2::SDL_WindowFlags::SDL_WINDOW_FULLSCREEN_DESKTOP as u32,
+        }
+    }
+
+    fn from_flags(flags: u32) -> Self {
+        let desktop = Fullscreen::Desktop.flags();
+        if flags & desktop == desktop {
+            Fullscreen::Desktop
+        } else if flags & Fullscreen::Exclusive.flags() != 0 {
+            Fullscreen::Exclusive
+        } else {
+            Fullscreen::Off
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WindowPos {
+    Undefined,
+    Centered,
+    At(i32),
+}
+
+impl WindowPos {
+    fn to_sdl(&self) -> i32 {
+        match self {
+            WindowPos::Undefined => sdl2::SDL_WINDOWPOS_UNDEFINED_MASK as i32,
+            WindowPos::Centered => sdl2::SDL_WINDOWPOS_CENTERED_MASK as i32,
+            WindowPos::At(v) => *v,
+        }
+    }
+}
+
+pub(crate) fn pixel_scale(size: Dimensions, drawable: Dimensions) -> DimensionsF {
+    if size.w == 0 || size.h == 0 {
+        return DimensionsF { w: 1.0, h: 1.0 };
+    }
+    DimensionsF {
+        w: drawable.w as f32 / size.w as f32,
+        h: drawable.h as f32 / size.h as f32,
+    }
+}
+
+// Reads bytes, which must outlive the returned SDL_RWops
+// SDL takes the size as an int, so larger inputs are an error
+pub(crate) fn rw_from_bytes(bytes: &[u8], context: &'static str) -> Result<*mut sdl2::SDL_RWops> {
+    let len = i32::try_from(bytes.len()).map_err(|_| {
+        Error::new(
+            context,
+            format!("{} bytes is more than SDL can read", bytes.len()),
+        )
+    })?;
+    let rw = unsafe { sdl2::SDL_RWFromConstMem(bytes.as_ptr() as *const _, len) };
+    if rw.is_null() {
+        Err(Error::sdl(context))
+    } else {
+        Ok(rw)
+    }
+}
+
+pub(crate) fn to_sdl_bool(b: bool) -> sdl2::SDL_bool {
+    if b {
+        sdl2::SDL_bool::SDL_TRUE
+    } else {
+        sdl2::SDL_bool::SDL_FALSE
+    }
+}
+
+// Surface loaded with SDL_image, used for window icons and cursors
+pub(crate) struct Surface {
+    pub(crate) s: NonNull<sdl2::SDL_Surface>,
+}
+
+impl Surface {
+    pub(crate) fn new(file: &str) -> Result<Self> {
+        let cstr = CString::new(file)?;
+        let s_ptr = unsafe { sdl2_image::IMG_Load(cstr.as_ptr()) };
+        match NonNull::new(s_ptr) {
+            Some(s) => Ok(Surface { s }),
+            None => Err(Error::sdl("Surface::new()")),
+        }
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        unsafe { sdl2::SDL_FreeSurface(self.s.as_ptr()) }
+    }
+}
+
+pub struct WindowBuilder {
+    title: String,
+    dim: Dimensions,
+    x: WindowPos,
+    y: WindowPos,
+    fullscreen: Fullscreen,
+    borderless: bool,
+    resizable: bool,
+    always_on_top: bool,
+    hidden: bool,
+    high_dpi: bool,
+    min_size: Option<Dimensions>,
+    max_size: Option<Dimensions>,
+    icon: Option<String>,
+}
+
+impl WindowBuilder {
     pub fn new() -> Self {
+        WindowBuilder {
+            title: String::new(),
+            dim: Dimensions { w: 640, h: 480 },
+            x: WindowPos::Centered,
+            y: WindowPos::Centered,
+            fullscreen: Fullscreen::Off,
+            borderless: false,
+            resizable: false,
+            always_on_top: false,
+            hidden: false,
+            high_dpi: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn dimensions(mut self, width: i32, height: i32) -> Self {
+        self.dim = Dimensions {
+            w: width,
+            h: height,
+        };
+        self
+    }
+
+    pub fn position(mut self, x: WindowPos, y: WindowPos) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    pub fn fullscreen(mut self, fullscreen: Fullscreen) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    pub fn borderless(mut self, borderless: bool) -> Self {
+        self.borderless = borderless;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    // Use the full resolution of high-DPI displays, the drawable size may then be
+    // larger than the window size
+    pub fn high_dpi(mut self, high_dpi: bool) -> Self {
+        self.high_dpi = high_dpi;
+        self
+    }
+
+    pub fn min_size(mut self, width: i32, height: i32) -> Self {
+        self.min_size = Some(Dimensions {
+            w: width,
+            h: height,
+        });
+        self
+    }
+
+    pub fn max_size(mut self, width: i32, height: i32) -> Self {
+        self.max_size = Some(Dimensions {
+            w: width,
+            h: height,
+        });
+        self
+    }
+
+    pub fn icon(mut self, file: &str) -> Self {
+        self.icon = Some(file.to_string());
+        self
+    }
+
+    pub fn build(self) -> Result<Window> {
+        let mut flags = self.fullscreen.flags();
+        flags |= if self.hidden {
+            sdl2::SDL_WindowFlags::SDL_WINDOW_HIDDEN as u32
+        } else {
+            sdl2::SDL_WindowFlags::SDL_WINDOW_SHOWN as u32
+        };
+        if self.borderless {
+            flags |= sdl2::SDL_WindowFlags::SDL_WINDOW_BORDERLESS as u32;
+        }
+        if self.resizable {
+            flags |= sdl2::SDL_WindowFlags::SDL_WINDOW_RESIZABLE as u32;
+        }
+        if self.always_on_top {
+            flags |= sdl2::SDL_WindowFlags::SDL_WINDOW_ALWAYS_ON_TOP as u32;
+        }
+        if self.high_dpi {
+            flags |= sdl2::SDL_WindowFlags::SDL_WINDOW_ALLOW_HIGHDPI as u32;
+        }
+        let title = CString::new(self.title)?;
         let w_ptr = unsafe {
             sdl2::SDL_CreateWindow(
-                CString::default().as_ptr(),
-                sdl2::SDL_WINDOWPOS_CENTERED_MASK as i32,
-                sdl2::SDL_WINDOWPOS_CENTERED_MASK as i32,
-                640,
-                480,
-                sdl2::SDL_WindowFlags::SDL_WINDOW_SHOWN as u32,
+                title.as_ptr(),
+                self.x.to_sdl(),
+                self.y.to_sdl(),
+                self.dim.w,
+                self.dim.h,
+                flags,
             )
         };
-        Window {
-            w: NonNull::new(w_ptr).expect("Failed to create window"),
+        let win = match NonNull::new(w_ptr) {
+            Some(w) => Window { w },
+            None => return Err(Error::sdl("WindowBuilder::build()")),
+        };
+        if let Some(dim) = self.min_size {
+            win.set_min_size(dim.w, dim.h);
         }
+        if let Some(dim) = self.max_size {
+            win.set_max_size(dim.w, dim.h);
+        }
+        if let Some(file) = self.icon {
+            win.set_icon(&file)?;
+        }
+        Ok(win)
+    }
+}
+
+pub struct Window {
+    w: NonNull<sdl2::SDL_Window>,
+}
+
+impl Window {
+    pub fn new() -> Result<Self> {
+        WindowBuilder::new().build()
+    }
+
+    pub fn builder() -> WindowBuilder {
+        WindowBuilder::new()
+    }
+
+    pub fn id(&self) -> u32 {
+        unsafe { sdl2::SDL_GetWindowID(self.w.as_ptr()) }
     }
 
     pub fn title(self, title: &str) -> Self {
-        let cstr = CString::new(title).expect("Failed to creat CString");
+        self.set_title(title);
+        self
+    }
+
+    pub fn dimensions(self, width: i32, height: i32) -> Self {
+        self.set_size(width, height);
+        self
+    }
+
+    pub fn set_title(&self, title: &str) {
+        // Interior nul bytes would truncate the title anyway
+        let cstr = CString::new(title.replace('\0', "")).unwrap_or_default();
         unsafe {
             sdl2::SDL_SetWindowTitle(self.w.as_ptr(), cstr.as_ptr());
         }
-        self
     }
 
-    pub fn dimensions(self, width: i32, height: i32) -> Self {
+    pub fn set_size(&self, width: i32, height: i32) {
         unsafe {
             sdl2::SDL_SetWindowSize(self.w.as_ptr(), width, height);
         }
-        self
+    }
+
+    pub fn size(&self) -> Dimensions {
+        let mut dim = Dimensions { w: 0, h: 0 };
+        unsafe {
+            sdl2::SDL_GetWindowSize(self.w.as_ptr(), &mut dim.w, &mut dim.h);
+        }
+        dim
+    }
+
+    // Size in pixels, differs from size() on high-DPI displays
+    pub fn drawable_size(&self) -> Dimensions {
+        let mut dim = Dimensions { w: 0, h: 0 };
+        unsafe {
+            sdl2::SDL_GetWindowSizeInPixels(self.w.as_ptr(), &mut dim.w, &mut dim.h);
+        }
+        dim
+    }
+
+    // Pixels per window unit
+    pub fn pixel_scale(&self) -> DimensionsF {
+        pixel_scale(self.size(), self.drawable_size())
+    }
+
+    pub fn display_index(&self) -> Result<i32> {
+        match unsafe { sdl2::SDL_GetWindowDisplayIndex(self.w.as_ptr()) } {
+            i if i < 0 => Err(Error::sdl("Window::display_index()")),
+            i => Ok(i),
+        }
+    }
+
+    pub fn set_position(&self, x: WindowPos, y: WindowPos) {
+        unsafe {
+            sdl2::SDL_SetWindowPosition(self.w.as_ptr(), x.to_sdl(), y.to_sdl());
+        }
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        let (mut x, mut y) = (0, 0);
+        unsafe {
+            sdl2::SDL_GetWindowPosition(self.w.as_ptr(), &mut x, &mut y);
+        }
+        (x, y)
+    }
+
+    pub fn set_min_size(&self, width: i32, height: i32) {
+        unsafe {
+            sdl2::SDL_SetWindowMinimumSize(self.w.as_ptr(), width, height);
+        }
+    }
+
+    pub fn set_max_size(&self, width: i32, height: i32) {
+        unsafe {
+            sdl2::SDL_SetWindowMaximumSize(self.w.as_ptr(), width, height);
+        }
+    }
+
+    pub fn set_borderless(&self, borderless: bool) {
+        unsafe {
+            sdl2::SDL_SetWindowBordered(self.w.as_ptr(), to_sdl_bool(!borderless));
+        }
+    }
+
+    pub fn set_resizable(&self, resizable: bool) {
+        unsafe {
+            sdl2::SDL_SetWindowResizable(self.w.as_ptr(), to_sdl_bool(resizable));
+        }
+    }
+
+    pub fn set_always_on_top(&self, always_on_top: bool) {
+        unsafe {
+            sdl2::SDL_SetWindowAlwaysOnTop(self.w.as_ptr(), to_sdl_bool(always_on_top));
+        }
+    }
+
+    // Confine the mouse to the window
+    pub fn set_mouse_grab(&self, grab: bool) {
+        unsafe {
+            sdl2::SDL_SetWindowMouseGrab(self.w.as_ptr(), to_sdl_bool(grab));
+        }
+    }
+
+    pub fn mouse_grabbed(&self) -> bool {
+        unsafe { sdl2::SDL_GetWindowMouseGrab(self.w.as_ptr()) == sdl2::SDL_bool::SDL_TRUE }
+    }
+
+    pub fn set_icon(&self, file: &str) -> Result<()> {
+        let surf = Surface::new(file)?;
+        unsafe {
+            sdl2::SDL_SetWindowIcon(self.w.as_ptr(), surf.s.as_ptr());
+        }
+        Ok(())
+    }
+
+    pub fn flags(&self) -> u32 {
+        unsafe { sdl2::SDL_GetWindowFlags(self.w.as_ptr()) }
+    }
+
+    pub fn fullscreen(&self) -> Fullscreen {
+        Fullscreen::from_flags(self.flags())
+    }
+
+    pub fn set_fullscreen(&self, fullscreen: Fullscreen) -> Result<()> {
+        if unsafe { sdl2::SDL_SetWindowFullscreen(self.w.as_ptr(), fullscreen.flags()) } != 0 {
+            return Err(Error::sdl("Window::set_fullscreen()"));
+        }
+        Ok(())
+    }
+
+    // Switch between windowed and the given fullscreen mode
+    pub fn toggle_fullscreen(&self, fullscreen: Fullscreen) -> Result<()> {
+        if self.fullscreen() == Fullscreen::Off {
+            self.set_fullscreen(fullscreen)
+        } else {
+            self.set_fullscreen(Fullscreen::Off)
+        }
     }
 }
 
@@ -54,11 +438,23 @@ pub struct Renderer {
 }
 
 impl Renderer {
-    pub fn new(win: &Window) -> Self {
+    pub fn new(win: &Window) -> Result<Self> {
         let r_ptr = unsafe { sdl2::SDL_CreateRenderer(win.w.as_ptr(), -1, 0) };
-        Renderer {
-            r: NonNull::new(r_ptr).expect("Failed to create renderer"),
+        match NonNull::new(r_ptr) {
+            Some(r) => Ok(Renderer { r }),
+            None => Err(Error::sdl("Renderer::new()")),
+        }
+    }
+
+    // Size of the render target in pixels
+    pub fn output_size(&self) -> Result<Dimensions> {
+        let mut dim = Dimensions { w: 0, h: 0 };
+        let res =
+            unsafe { sdl2::SDL_GetRendererOutputSize(self.r.as_ptr(), &mut dim.w, &mut dim.h) };
+        if res != 0 {
+            return Err(Error::sdl("Renderer::output_size()"));
         }
+        Ok(dim)
     }
 
     pub fn clear(&self) {
@@ -87,19 +483,84 @@ pub trait TextureTrait {
 
 pub struct Texture {
     tex: NonNull<sdl2::SDL_Texture>,
+    // Handed out as Weak to every TextureAccess, dropped with the texture
+    alive: Rc<()>,
 }
 
 impl Texture {
-    pub fn new(r: &Renderer, file: &str) -> Self {
-        let cstr = CString::new(file).expect("Failed to creat CString");
+    fn from_ptr(t_ptr: *mut sdl2::SDL_Texture, context: &'static str) -> Result<Self> {
+        match NonNull::new(t_ptr) {
+            Some(tex) => Ok(Texture {
+                tex,
+                alive: Rc::new(()),
+            }),
+            None => Err(Error::sdl(context)),
+        }
+    }
+
+    pub fn new(r: &Renderer, file: &str) -> Result<Self> {
+        let cstr = CString::new(file)?;
         let t_ptr = unsafe { sdl2_image::IMG_LoadTexture(r.r.as_ptr(), cstr.as_ptr()) };
-        Texture {
-            tex: NonNull::new(t_ptr).expect("Failed to create Texture"),
+        Texture::from_ptr(t_ptr, "Texture::new()")
+    }
+
+    pub fn from_bytes(r: &Renderer, bytes: &[u8]) -> Result<Self> {
+        let rw = rw_from_bytes(bytes, "Texture::from_bytes()")?;
+        let t_ptr = unsafe { sdl2_image::IMG_LoadTexture_RW(r.r.as_ptr(), rw, 1) };
+        Texture::from_ptr(t_ptr, "Texture::from_bytes()")
+    }
+
+    // Magenta and black checkerboard with tiles of tile_size pixels
+    pub fn checkerboard(r: &Renderer, size: i32, tile_size: i32) -> Result<Self> {
+        unsafe {
+            let surf = sdl2::SDL_CreateRGBSurfaceWithFormat(
+                0,
+                size,
+                size,
+                32,
+                sdl2::SDL_PixelFormatEnum::SDL_PIXELFORMAT_RGBA8888 as u32,
+            );
+            if surf.is_null() {
+                return Err(Error::sdl("Texture::checkerboard()"));
+            }
+            let black = sdl2::SDL_MapRGB((*surf).format, 0x00, 0x00, 0x00);
+            let magenta = sdl2::SDL_MapRGB((*surf).format, 0xFF, 0x00, 0xFF);
+            let tile_size = tile_size.max(1);
+            for y in (0..size).step_by(tile_size as usize) {
+                for x in (0..size).step_by(tile_size as usize) {
+                    let rect = sdl2::SDL_Rect {
+                        x,
+                        y,
+                        w: tile_size,
+                        h: tile_size,
+                    };
+                    let color = if (x / tile_size + y / tile_size) % 2 == 0 {
+                        magenta
+                    } else {
+                        black
+                    };
+                    sdl2::SDL_FillRect(surf, &rect, color);
+                }
+            }
+            let t_ptr = sdl2::SDL_CreateTextureFromSurface(r.r.as_ptr(), surf);
+            sdl2::SDL_FreeSurface(surf);
+            Texture::from_ptr(t_ptr, "Texture::checkerboard()")
         }
     }
 
     pub fn access(&self) -> TextureAccess {
-        TextureAccess { tex: self.tex }
+        TextureAccess {
+            tex: self.tex,
+            alive: Rc::downgrade(&self.alive),
+        }
+    }
+
+    pub fn query(&self) -> Result<TextureInfo> {
+        self.access().query()
+    }
+
+    pub fn size(&self) -> Dimensions {
+        self.access().size()
     }
 }
 
@@ -109,16 +570,104 @@ impl Drop for Texture {
     }
 }
 
-// TextureAccess
+// TextureInfo
 #[derive(Copy, Clone)]
+pub struct TextureInfo {
+    // SDL_PixelFormatEnum value
+    pub format: u32,
+    pub access: sdl2::SDL_TextureAccess,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl TextureInfo {
+    pub fn dim(&self) -> Dimensions {
+        Dimensions {
+            w: self.w,
+            h: self.h,
+        }
+    }
+
+    pub fn format_name(&self) -> String {
+        unsafe { CStr::from_ptr(sdl2::SDL_GetPixelFormatName(self.format)) }
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+// TextureAccess
+// A handle to a Texture owned elsewhere, e.g. by the AssetManager
+// Once the texture is released the handle is invalid and draws nothing
+#[derive(Clone)]
 pub struct TextureAccess {
-    pub(crate) tex: NonNull<sdl2::SDL_Texture>,
+    tex: NonNull<sdl2::SDL_Texture>,
+    alive: Weak<()>,
 }
 
 impl TextureTrait for TextureAccess {
     fn draw(&self, r: &Renderer, src: *const sdl2::SDL_Rect, dest: *const sdl2::SDL_Rect) {
-        unsafe {
-            sdl2::SDL_RenderCopy(r.r.as_ptr(), self.tex.as_ptr(), src, dest);
+        if let Some(tex) = self.ptr() {
+            unsafe {
+                sdl2::SDL_RenderCopy(r.r.as_ptr(), tex, src, dest);
+            }
         }
     }
 }
+
+impl TextureAccess {
+    pub fn valid(&self) -> bool {
+        self.alive.strong_count() > 0
+    }
+
+    fn ptr(&self) -> Option<*mut sdl2::SDL_Texture> {
+        if self.valid() {
+            Some(self.tex.as_ptr())
+        } else {
+            None
+        }
+    }
+
+    pub fn query(&self) -> Result<TextureInfo> {
+        let tex = match self.ptr() {
+            Some(tex) => tex,
+            None => {
+                return Err(Error::new(
+                    "TextureAccess::query()",
+                    "Texture has been released",
+                ))
+            }
+        };
+        let (mut format, mut access, mut w, mut h) = (0, 0, 0, 0);
+        if unsafe { sdl2::SDL_QueryTexture(tex, &mut format, &mut access, &mut w, &mut h) } != 0 {
+            return Err(Error::sdl("TextureAccess::query()"));
+        }
+        match FromPrimitive::from_i32(access) {
+            Some(access) => Ok(TextureInfo {
+                format,
+                access,
+                w,
+                h,
+            }),
+            None => Err(Error::new(
+                "TextureAccess::query()",
+                format!("Unknown texture access {}", access),
+            )),
+        }
+    }
+
+    // 0x0 if the texture can't be queried
+    pub fn size(&self) -> Dimensions {
+        match self.query() {
+            Ok(info) => info.dim(),
+            Err(_) => Dimensions { w: 0, h: 0 },
+        }
+    }
+
+    pub fn format(&self) -> Option<u32> {
+        self.query().ok().map(|info| info.format)
+    }
+
+    pub fn access_mode(&self) -> Option<sdl2::SDL_TextureAccess> {
+        self.query().ok().map(|info| info.access)
+    }
+}
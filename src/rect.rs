@@ -59,6 +59,47 @@ impl Rect {
         r
     }
 
+    // Natural size, e.g. of a texture
+    pub fn from_dim(x: f32, y: f32, dim: Dimensions, ax: Align, ay: Align) -> Self {
+        Rect::from(x, y, dim.w as f32, dim.h as f32, ax, ay)
+    }
+
+    // Height follows from the aspect ratio of dim
+    pub fn from_width(x: f32, y: f32, w: f32, dim: Dimensions, ax: Align, ay: Align) -> Self {
+        let h = if dim.w == 0 {
+            0.0
+        } else {
+            w * dim.h as f32 / dim.w as f32
+        };
+        Rect::from(x, y, w, h, ax, ay)
+    }
+
+    // Width follows from the aspect ratio of dim
+    pub fn from_height(x: f32, y: f32, h: f32, dim: Dimensions, ax: Align, ay: Align) -> Self {
+        let w = if dim.h == 0 {
+            0.0
+        } else {
+            h * dim.w as f32 / dim.h as f32
+        };
+        Rect::from(x, y, w, h, ax, ay)
+    }
+
+    // Largest rect with the aspect ratio of dim that fits in bounds, aligned within bounds
+    pub fn fit_aspect(bounds: &Rect, dim: Dimensions, ax: Align, ay: Align) -> Self {
+        if dim.w == 0 || dim.h == 0 {
+            return Rect::from(bounds.get_x(ax), bounds.get_y(ay), 0.0, 0.0, ax, ay);
+        }
+        let scale = (bounds.w / dim.w as f32).min(bounds.h / dim.h as f32);
+        Rect::from(
+            bounds.get_x(ax),
+            bounds.get_y(ay),
+            dim.w as f32 * scale,
+            dim.h as f32 * scale,
+            ax,
+            ay,
+        )
+    }
+
     pub fn from_corners(x1: f32, y1: f32, x2: f32, y2: f32) -> Self {
         let (x, w) = if x1 < x2 {
             (x1, x2 - x1)
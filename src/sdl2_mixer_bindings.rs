@@ -0,0 +1,4 @@
+#[allow(warnings)]
+pub mod sdl2_mixer_ {
+    include!(concat!(env!("OUT_DIR"), "/sdl2_mixer_bindings.rs"));
+}
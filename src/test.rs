@@ -1,7 +1,9 @@
-use crate::{enum_type, enum_union, message};
-use enums::*;
-use message::*;
-use test::*;
+use crate::enums::*;
+use crate::message::*;
+use crate::{enum_type, enum_union, enums, message};
+
+use std::cell::RefCell;
+use std::rc::Rc;
 
 enum_type!(A, Y, Z);
 enum_type!(B, S, T);
@@ -10,46 +12,45 @@ enum_union!(AB, A, B);
 message!(MyMessage, MyMessageEnum, A, B);
 message!(OtherMessage, OtherMessageEnum, A);
 
+#[test]
 fn do_test() {
-    println!("Test");
-
-    println!(
-        "{} {} {}",
-        A::Y.equals(&A::Y),
-        A::Y.equals(&A::Z),
-        A::Y.equals(&B::T)
-    );
+    assert!(A::Y.equals(&A::Y));
+    assert!(!A::Y.equals(&A::Z));
+    assert!(!A::Y.equals(&B::T));
 
     let ab = AB::A(A::Y);
-    println!("{} {} {}", ab == A::Y, ab == A::Z, ab == B::T);
-    println!(
-        "{} {} {}",
-        ab.equals(&A::Y),
-        ab.equals(&A::Z),
-        ab.equals(&B::T)
-    );
-
-    println!(
-        "{} {} {} {}",
-        A::Y.to_str(),
-        A::Z.to_str(),
-        B::T.to_str(),
-        ab.to_str()
-    );
+    assert!(ab == A::Y);
+    assert!(ab != A::Z);
+    assert!(ab != B::T);
+    assert!(ab.equals(&A::Y));
+    assert!(!ab.equals(&A::Z));
+    assert!(!ab.equals(&B::T));
+
+    // Values stringify to their type
+    assert_eq!(A::Y.to_str(), "A");
+    assert_eq!(A::Z.to_str(), "A");
+    assert_eq!(B::T.to_str(), "B");
+    assert_eq!(ab.to_str(), "A");
 
     let msg = MyMessage::new(A::Y);
     let msg2 = MyMessage::new(B::S);
-    println!("{} {}", msg.get_code(), msg2.get_code());
+    assert_eq!(msg.get_code(), MyMessageEnum::A(A::Y));
+    assert_eq!(msg2.get_code(), MyMessageEnum::B(B::S));
 
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let r = received.clone();
     let mut mb = message::MessageBus::new();
-    let sub = mb.subscribe::<MyMessageEnum, MyMessage>(&|v: &MyMessageEnum| {
-        println!("Hello From Callback {}", v)
-    });
-    println!("Sub Id: {}", sub.get_id());
+    let sub =
+        mb.subscribe::<MyMessageEnum, MyMessage>(move |v: &MyMessageEnum| r.borrow_mut().push(*v));
+    assert_eq!(sub.get_id(), 0);
     mb.send_message(msg);
+    // No subscribers
     mb.send_message(OtherMessage::new(A::Z));
+    // No subscription with this id, nothing is removed
     mb.unsubscribe(message::SubscriptionHandle::<MyMessageEnum, MyMessage>::new(2));
+    assert!(mb.has_subscribers::<MyMessageEnum, MyMessage>());
     mb.unsubscribe(sub);
+    assert!(!mb.has_subscribers::<MyMessageEnum, MyMessage>());
     mb.send_message(msg2);
-    println!("Shouldn't print");
+    assert_eq!(*received.borrow(), [MyMessageEnum::A(A::Y)]);
 }
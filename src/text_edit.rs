@@ -0,0 +1,378 @@
+use crate::clipboard;
+use crate::event::{Event, InputSeek};
+use crate::rect::Rect;
+use crate::sdl2;
+
+const MAX_UNDO: usize = 100;
+
+#[derive(Clone)]
+struct Snapshot {
+    text: String,
+    cursor: usize,
+    anchor: Option<usize>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum EditKind {
+    None,
+    // Consecutive typing is undone in one step
+    Typing,
+    Other,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// TextEdit
+// A single line text field fed from Event's text input state once per frame
+// Positions are byte offsets into the UTF-8 text and always lie on char boundaries
+pub struct TextEdit {
+    text: String,
+    cursor: usize,
+    // Other end of the selection, the cursor is the moving end
+    anchor: Option<usize>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: EditKind,
+    // Limit in chars, None is unlimited
+    pub max_chars: Option<usize>,
+    // Uncommitted IME text, shown at the cursor but not part of text
+    composition: String,
+    composition_cursor: usize,
+}
+
+impl TextEdit {
+    pub fn new() -> Self {
+        TextEdit::from_text("")
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        TextEdit {
+            text: text.to_string(),
+            cursor: text.len(),
+            anchor: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit: EditKind::None,
+            max_chars: None,
+            composition: String::new(),
+            composition_cursor: 0,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Replaces the text and clears the undo history
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = text.len();
+        self.anchor = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.last_edit = EditKind::None;
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // Cursor position in chars, e.g. for measuring the text in front of it
+    pub fn cursor_chars(&self) -> usize {
+        self.text[..self.cursor].chars().count()
+    }
+
+    // Ordered byte range, None if nothing is selected
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        match self.anchor {
+            Some(a) if a != self.cursor => Some((a.min(self.cursor), a.max(self.cursor))),
+            _ => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> &str {
+        match self.selection() {
+            Some((start, end)) => &self.text[start..end],
+            None => "",
+        }
+    }
+
+    pub fn composition(&self) -> &str {
+        &self.composition
+    }
+
+    // Text as it should be drawn, with the IME composition at the cursor
+    pub fn display_text(&self) -> String {
+        let mut s = self.text.clone();
+        s.insert_str(self.cursor, &self.composition);
+        s
+    }
+
+    // Byte range of the composition within display_text(), draw it underlined
+    pub fn display_composition(&self) -> Option<(usize, usize)> {
+        if self.composition.is_empty() {
+            None
+        } else {
+            Some((self.cursor, self.cursor + self.composition.len()))
+        }
+    }
+
+    // Cursor position within display_text(), inside the composition while composing
+    pub fn display_cursor(&self) -> usize {
+        self.cursor
+            + self
+                .composition
+                .char_indices()
+                .nth(self.composition_cursor)
+                .map_or(self.composition.len(), |(i, _)| i)
+    }
+
+    // Start receiving text, rect is where the field is drawn in window coordinates
+    // The IME places its candidate list next to it
+    pub fn start_input(&self, rect: &Rect) {
+        unsafe { sdl2::SDL_StartTextInput() };
+        self.set_input_rect(rect);
+    }
+
+    pub fn stop_input(&mut self) {
+        unsafe { sdl2::SDL_StopTextInput() };
+        self.composition.clear();
+        self.composition_cursor = 0;
+    }
+
+    // Call again when the field moves
+    pub fn set_input_rect(&self, rect: &Rect) {
+        let r = rect.to_sdl_rect();
+        unsafe { sdl2::SDL_SetTextInputRect(&r) };
+    }
+
+    // Apply this frame's text input, returns true if the text changed
+    pub fn update(&mut self, e: &Event) -> bool {
+        let old_text = self.text.clone();
+        self.composition = e.input_composition.clone();
+        self.composition_cursor = e.input_composition_cursor.max(0) as usize;
+        if e.input_undo {
+            self.undo();
+        }
+        if e.input_redo {
+            self.redo();
+        }
+        if e.input_select_all {
+            self.select_all();
+        }
+        if e.input_copy {
+            self.copy();
+        }
+        if e.input_cut {
+            self.cut();
+        }
+        for _ in 0..e.input_backspace {
+            self.backspace();
+        }
+        for _ in 0..e.input_delete {
+            self.delete();
+        }
+        if !e.input_text.is_empty() {
+            self.insert(&e.input_text);
+        }
+        if e.input_move != 0 {
+            self.move_by(e.input_move, e.input_select);
+        }
+        if e.input_word_move != 0 {
+            self.move_words(e.input_word_move, e.input_select);
+        }
+        match e.input_seek {
+            InputSeek::Start => self.move_to(0, e.input_select),
+            InputSeek::End => self.move_to(self.text.len(), e.input_select),
+            InputSeek::None => {}
+        }
+        self.text != old_text
+    }
+
+    // Replaces the selection, typing only merges with the previous undo step
+    pub fn insert(&mut self, s: &str) {
+        let room = self.max_chars.map_or(usize::MAX, |max| {
+            let selected = self.selected_text().chars().count();
+            (max + selected).saturating_sub(self.text.chars().count())
+        });
+        let s: String = s.chars().take(room).collect();
+        if s.is_empty() {
+            return;
+        }
+        self.begin_edit(EditKind::Typing);
+        self.delete_selection();
+        self.text.insert_str(self.cursor, &s);
+        self.cursor += s.len();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.selection().is_some() {
+            self.begin_edit(EditKind::Other);
+            self.delete_selection();
+        } else if self.cursor > 0 {
+            self.begin_edit(EditKind::Other);
+            let start = self.prev_boundary(self.cursor);
+            self.text.replace_range(start..self.cursor, "");
+            self.cursor = start;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.selection().is_some() {
+            self.begin_edit(EditKind::Other);
+            self.delete_selection();
+        } else if self.cursor < self.text.len() {
+            self.begin_edit(EditKind::Other);
+            let end = self.next_boundary(self.cursor);
+            self.text.replace_range(self.cursor..end, "");
+        }
+    }
+
+    // Move by n chars, extending the selection if select is set
+    pub fn move_by(&mut self, n: i32, select: bool) {
+        // Without shift an arrow key first collapses the selection to its edge
+        if let (Some((start, end)), false) = (self.selection(), select) {
+            self.move_to(if n < 0 { start } else { end }, false);
+            return;
+        }
+        let mut pos = self.cursor;
+        for _ in 0..n.unsigned_abs() {
+            pos = if n < 0 {
+                self.prev_boundary(pos)
+            } else {
+                self.next_boundary(pos)
+            };
+        }
+        self.move_to(pos, select);
+    }
+
+    // Move to the start of the previous or next n words
+    pub fn move_words(&mut self, n: i32, select: bool) {
+        let mut pos = self.cursor;
+        for _ in 0..n.unsigned_abs() {
+            pos = if n < 0 {
+                self.prev_word(pos)
+            } else {
+                self.next_word(pos)
+            };
+        }
+        self.move_to(pos, select);
+    }
+
+    pub fn move_to(&mut self, pos: usize, select: bool) {
+        let mut pos = pos.min(self.text.len());
+        while !self.text.is_char_boundary(pos) {
+            pos -= 1;
+        }
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = pos;
+        self.last_edit = EditKind::None;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    pub fn copy(&self) {
+        let s = self.selected_text();
+        if !s.is_empty() {
+            if let Err(e) = clipboard::set_clipboard_text(s) {
+                println!("TextEdit::copy() - {}", e);
+            }
+        }
+    }
+
+    pub fn cut(&mut self) {
+        if self.selection().is_some() {
+            self.copy();
+            self.begin_edit(EditKind::Other);
+            self.delete_selection();
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(s) = self.undo.pop() {
+            self.redo.push(self.snapshot());
+            self.restore(s);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(s) = self.redo.pop() {
+            self.undo.push(self.snapshot());
+            self.restore(s);
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            text: self.text.clone(),
+            cursor: self.cursor,
+            anchor: self.anchor,
+        }
+    }
+
+    fn restore(&mut self, s: Snapshot) {
+        self.text = s.text;
+        self.cursor = s.cursor;
+        self.anchor = s.anchor;
+        self.last_edit = EditKind::None;
+    }
+
+    // Save an undo step before changing the text
+    fn begin_edit(&mut self, kind: EditKind) {
+        if kind != EditKind::Typing || self.last_edit != EditKind::Typing {
+            self.undo.push(self.snapshot());
+            if self.undo.len() > MAX_UNDO {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.last_edit = kind;
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some((start, end)) = self.selection() {
+            self.text.replace_range(start..end, "");
+            self.cursor = start;
+        }
+        self.anchor = None;
+    }
+
+    fn prev_boundary(&self, pos: usize) -> usize {
+        self.text[..pos]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self, pos: usize) -> usize {
+        pos + self.text[pos..].chars().next().map_or(0, |c| c.len_utf8())
+    }
+
+    // Skip separators, then the word before pos
+    fn prev_word(&self, pos: usize) -> usize {
+        let mut chars = self.text[..pos].char_indices().rev().peekable();
+        while chars.next_if(|(_, c)| !is_word_char(*c)).is_some() {}
+        let mut start = chars.peek().map_or(0, |(i, _)| *i);
+        while let Some((i, _)) = chars.next_if(|(_, c)| is_word_char(*c)) {
+            start = i;
+        }
+        start
+    }
+
+    // Skip the rest of the current word, then separators
+    fn next_word(&self, pos: usize) -> usize {
+        let mut chars = self.text[pos..].char_indices().peekable();
+        while chars.next_if(|(_, c)| is_word_char(*c)).is_some() {}
+        while chars.next_if(|(_, c)| !is_word_char(*c)).is_some() {}
+        pos + chars.peek().map_or(self.text.len() - pos, |(i, _)| *i)
+    }
+}
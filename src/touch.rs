@@ -0,0 +1,131 @@
+use crate::event::Status;
+use crate::rect::{PointF, Rect};
+
+// A finger on a touch device
+// Positions are normalized to 0 - 1 across the window
+#[derive(Copy, Clone)]
+pub struct Finger {
+    pub id: i64,
+    pub touch_id: i64,
+    pub pos: PointF,
+    // pos mapped through the camera
+    pub world: PointF,
+    // Movement this frame
+    pub delta: PointF,
+    pub pressure: f32,
+    pub duration: u32,
+    pub(crate) status: u8,
+}
+
+impl Finger {
+    pub(crate) fn new(touch_id: i64, id: i64, pos: PointF, pressure: f32) -> Self {
+        Finger {
+            id,
+            touch_id,
+            pos,
+            world: pos,
+            delta: PointF { x: 0.0, y: 0.0 },
+            pressure,
+            duration: 0,
+            status: Status::Down as u8 | Status::Held as u8,
+        }
+    }
+
+    pub fn down(&self) -> bool {
+        self.status & Status::Down as u8 != 0
+    }
+
+    pub fn up(&self) -> bool {
+        self.status & Status::Up as u8 != 0
+    }
+
+    pub fn held(&self) -> bool {
+        self.status & Status::Held as u8 != 0
+    }
+}
+
+// Normalized window position to camera coordinates
+pub fn touch_to_world(camera: &Rect, p: PointF) -> PointF {
+    PointF {
+        x: camera.x() + p.x * camera.w(),
+        y: camera.y() + p.y * camera.h(),
+    }
+}
+
+// Multi-finger gesture this frame, from SDL_MULTIGESTURE
+// Values are summed over the frame and 0 when no gesture happened
+#[derive(Copy, Clone)]
+pub struct Gesture {
+    pub fingers: u16,
+    // Normalized centroid of the fingers
+    pub center: PointF,
+    // Change in the average distance of the fingers from center, normalized
+    pub pinch: f32,
+    // Rotation of the fingers around center in radians
+    pub rotate: f32,
+    // Movement of center, normalized
+    pub pan: PointF,
+    // Last center of the ongoing gesture, pan is measured from it
+    pub(crate) last_center: Option<PointF>,
+}
+
+impl Gesture {
+    pub(crate) fn new() -> Self {
+        Gesture {
+            fingers: 0,
+            center: PointF { x: 0.0, y: 0.0 },
+            pinch: 0.0,
+            rotate: 0.0,
+            pan: PointF { x: 0.0, y: 0.0 },
+            last_center: None,
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.last_center.is_some()
+    }
+
+    pub(crate) fn reset_frame(&mut self) {
+        self.pinch = 0.0;
+        self.rotate = 0.0;
+        self.pan = PointF { x: 0.0, y: 0.0 };
+    }
+
+    pub(crate) fn update(&mut self, fingers: u16, center: PointF, pinch: f32, rotate: f32) {
+        if let Some(last) = self.last_center {
+            self.pan.x += center.x - last.x;
+            self.pan.y += center.y - last.y;
+        }
+        self.fingers = fingers;
+        self.center = center;
+        self.pinch += pinch;
+        self.rotate += rotate;
+        self.last_center = Some(center);
+    }
+
+    // Called when fewer than two fingers are down
+    pub(crate) fn end(&mut self) {
+        self.fingers = 0;
+        self.last_center = None;
+    }
+
+    // Zoom factor of a pinch given the current fingers, > 1 when spreading
+    // Multiply a camera's size by the inverse to zoom in with the fingers
+    pub fn zoom(&self, fingers: &[Finger]) -> f32 {
+        let held: Vec<&Finger> = fingers.iter().filter(|f| f.held()).collect();
+        if held.len() < 2 || self.pinch == 0.0 {
+            return 1.0;
+        }
+        let spread = held
+            .iter()
+            .map(|f| ((f.pos.x - self.center.x).powi(2) + (f.pos.y - self.center.y).powi(2)).sqrt())
+            .sum::<f32>()
+            / held.len() as f32;
+        let prev = spread - self.pinch;
+        if prev <= 0.0 {
+            1.0
+        } else {
+            spread / prev
+        }
+    }
+}